@@ -0,0 +1,104 @@
+//! Counts allocations when decoding a burst of messages into owned types
+//! versus borrowing from a `Frame`.
+//!
+//! Run natively, since the crate defaults to the wasm32 target:
+//!
+//! ```sh
+//! cargo bench --bench borrowed --target x86_64-unknown-linux-gnu
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use serde::Deserialize;
+
+const MESSAGES: usize = 10_000;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct OwnedTrade {
+    id: u64,
+    symbol: String,
+    side: String,
+    venue: String,
+    price: f64,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct BorrowedTrade<'a> {
+    id: u64,
+    symbol: &'a str,
+    side: &'a str,
+    venue: &'a str,
+    price: f64,
+}
+
+fn messages() -> Vec<String> {
+    (0..MESSAGES)
+        .map(|id| {
+            format!(
+                r#"{{"id":{},"symbol":"BTC-USD","side":"buy","venue":"exchange-{}","price":{}.5}}"#,
+                id,
+                id % 8,
+                20_000 + id
+            )
+        })
+        .collect()
+}
+
+// Runs `decode` over every message and prints the number of allocations made
+// while decoding.
+fn measure(name: &str, decode: impl Fn(&mut Vec<u8>)) {
+    let mut burst: Vec<Vec<u8>> = messages().into_iter().map(String::into_bytes).collect();
+
+    let start = Instant::now();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for bytes in &mut burst {
+        decode(bytes);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    println!(
+        "{:<10} {:>8} allocations for {} messages ({:.1} per message) in {:?}",
+        name,
+        allocations,
+        MESSAGES,
+        allocations as f64 / MESSAGES as f64,
+        start.elapsed()
+    );
+}
+
+fn main() {
+    measure("owned", |bytes| {
+        let trade: OwnedTrade = serde_json::from_slice(bytes).unwrap();
+        std::hint::black_box(trade);
+    });
+
+    measure("borrowed", |bytes| {
+        // What `Frame::json` does.
+        let trade: BorrowedTrade = serde_json::from_slice(bytes).unwrap();
+        std::hint::black_box(trade);
+    });
+}
@@ -1,7 +1,7 @@
 use std::rc::Rc;
 
 use dioxus::prelude::*;
-use dioxus_websocket_hooks::{use_ws_context, use_ws_context_provider_json};
+use dioxus_websocket_hooks::{use_typed_ws_context, use_typed_ws_context_provider};
 use fermi::{use_init_atom_root, use_read, use_set, Atom};
 use serde::{Deserialize, Serialize};
 
@@ -30,14 +30,20 @@ fn app(cx: Scope) -> Element {
     use_init_atom_root(cx);
     let set_response = Rc::clone(use_set(cx, WS_RESPONSE_ATOM));
 
-    use_ws_context_provider_json(cx, "wss://echo.websocket.events", move |msg| {
-        // Handler for incoming parsed JSON websocket messages.
+    // Incoming messages are parsed as WsResponse, and children may only send
+    // WsRequest.
+    use_typed_ws_context_provider::<WsResponse, WsRequest>(
+        cx,
+        "wss://echo.websocket.events",
+        move |msg| {
+            // Handler for incoming parsed JSON websocket messages.
 
-        // In this example we just set the response atom to the received message.
-        // You could also match over the enum and perform different actions here
-        // as needed.
-        set_response(msg)
-    });
+            // In this example we just set the response atom to the received message.
+            // You could also match over the enum and perform different actions here
+            // as needed.
+            set_response(Some(msg))
+        },
+    );
 
     cx.render(rsx!(ResponseDisplay {}))
 }
@@ -63,21 +69,33 @@ fn ResponseDisplay(cx: Scope) -> Element {
 // We can also send websocket messages from other components:
 #[allow(non_snake_case)]
 fn SendA(cx: Scope) -> Element {
-    let ws = use_ws_context(cx);
-    let onclick = move |_| ws.send_json(&WsRequest::A);
+    let ws = use_typed_ws_context::<WsRequest>(cx);
+    let onclick = move |_| {
+        if let Err(err) = ws.send(&WsRequest::A) {
+            log::error!("Could not send message: {}", err);
+        }
+    };
     cx.render(rsx!( button { onclick: onclick, "A" } ))
 }
 
 #[allow(non_snake_case)]
 fn SendB(cx: Scope) -> Element {
-    let ws = use_ws_context(cx);
-    let onclick = move |_| ws.send_json(&WsRequest::B);
+    let ws = use_typed_ws_context::<WsRequest>(cx);
+    let onclick = move |_| {
+        if let Err(err) = ws.send(&WsRequest::B) {
+            log::error!("Could not send message: {}", err);
+        }
+    };
     cx.render(rsx!( button { onclick: onclick, "B" } ))
 }
 
 #[allow(non_snake_case)]
 fn SendC(cx: Scope) -> Element {
-    let ws = use_ws_context(cx);
-    let onclick = move |_| ws.send_json(&WsRequest::C);
+    let ws = use_typed_ws_context::<WsRequest>(cx);
+    let onclick = move |_| {
+        if let Err(err) = ws.send(&WsRequest::C) {
+            log::error!("Could not send message: {}", err);
+        }
+    };
     cx.render(rsx!( button { onclick: onclick, "C" } ))
 }
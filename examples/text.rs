@@ -28,7 +28,9 @@ fn ResponseDisplay(cx: Scope) -> Element {
 
     let input = use_state(cx, String::default);
     let submit = move |_| {
-        ws.send_text(input.to_string());
+        if let Err(err) = ws.send_text(input.to_string()) {
+            log::error!("Could not send message: {}", err);
+        }
         input.modify(|_| String::default());
     };
 
@@ -0,0 +1,185 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_sockets::Message;
+
+use crate::{
+    codec::json_error, use_provider, CodecError, DioxusWs, SendError, SendOutcome, WsConfig,
+    WsInner,
+};
+
+impl<Tag> DioxusWs<Tag> {
+    /// Sends several values as newline-delimited JSON in a single message.
+    pub fn send_json_batch<T: Serialize>(&self, values: &[T]) -> Result<SendOutcome, SendError> {
+        let mut batch = String::new();
+        for value in values {
+            batch.push_str(&serde_json::to_string(value).map_err(CodecError::from)?);
+            batch.push('\n');
+        }
+
+        let msg = if self.inner.config.json.binary_output {
+            Message::Binary(batch.into_bytes())
+        } else {
+            Message::Text(batch)
+        };
+        self.send(msg)
+    }
+}
+
+/// Provide websocket context with a handler for incoming batches of JSON
+/// messages. The handler is called once for every value in a message.
+///
+/// A message may hold newline-delimited (or whitespace separated) JSON
+/// values, or a JSON array whose elements are the values. Because of the
+/// latter, `T` itself must not be a JSON array.
+///
+/// Values that fail to decode are reported like any other decode error, see
+/// [`WsConfig::on_decode_error`], and don't affect the rest of the batch. A
+/// syntax error ends the batch, since the values after it can't be located.
+pub fn use_ws_context_provider_json_batch<T>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_batch_tagged::<(), T>(cx, url, handler)
+}
+
+/// Like [`use_ws_context_provider_json_batch`], using the given connection
+/// options. Binary messages are only accepted with
+/// [`Json::accept_binary`](crate::Json::accept_binary) set in
+/// [`WsConfig::json`].
+pub fn use_ws_context_provider_json_batch_with_config<T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_batch_with_config_tagged::<(), T>(cx, url, config, handler)
+}
+
+/// Like [`use_ws_context_provider_json_batch`], for the connection identified
+/// by `Tag`.
+pub fn use_ws_context_provider_json_batch_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_batch_with_config_tagged::<Tag, T>(
+        cx,
+        url,
+        WsConfig::default(),
+        handler,
+    )
+}
+
+/// Like [`use_ws_context_provider_json_batch_with_config`], for the
+/// connection identified by `Tag`.
+pub fn use_ws_context_provider_json_batch_with_config_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    let handler = move |inner: &WsInner, msg: Message| {
+        if matches!(msg, Message::Binary(_)) && !inner.config.json.accept_binary {
+            inner.decode_failed(msg, CodecError::UnexpectedBinary);
+            return;
+        }
+
+        let bytes = match &msg {
+            Message::Text(text) => text.as_bytes(),
+            Message::Binary(bytes) => bytes.as_slice(),
+        };
+
+        let mut failures = Vec::new();
+        decode_batch::<T>(bytes, |result| match result {
+            Ok(value) => handler(value),
+            Err(e) => failures.push(e),
+        });
+
+        for e in failures {
+            inner.decode_failed(msg.clone(), e);
+        }
+    };
+
+    use_provider::<Tag>(cx, url, config, handler);
+}
+
+// Decodes every value in a batch, calling `each` with the result for each one.
+fn decode_batch<T>(bytes: &[u8], mut each: impl FnMut(Result<T, CodecError>))
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let values = serde_json::Deserializer::from_slice(bytes).into_iter::<Value>();
+
+    for value in values {
+        match value {
+            // A top-level array holds a batch of its own.
+            Ok(Value::Array(items)) => {
+                for item in items {
+                    each(decode_value(item));
+                }
+            }
+            Ok(value) => each(decode_value(value)),
+            Err(e) => {
+                each(Err(e.into()));
+                break;
+            }
+        }
+    }
+}
+
+fn decode_value<T>(value: Value) -> Result<T, CodecError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    serde_path_to_error::deserialize(value).map_err(json_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Event {
+        id: u32,
+    }
+
+    fn decode(input: &str) -> Vec<Result<Event, CodecError>> {
+        let mut results = Vec::new();
+        decode_batch(input.as_bytes(), |result| results.push(result));
+        results
+    }
+
+    #[test]
+    fn reports_bad_values_without_dropping_good_ones() {
+        let results =
+            decode("{\"id\": 1}\n{\"id\": \"two\"}\n[{\"id\": 3}, {\"name\": 4}]\n{\"id\": 5}");
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap(), &Event { id: 1 });
+        assert_eq!(results[1].as_ref().unwrap_err().path(), Some("id"));
+        assert_eq!(results[2].as_ref().unwrap(), &Event { id: 3 });
+        assert!(results[3].is_err());
+        assert_eq!(results[4].as_ref().unwrap(), &Event { id: 5 });
+    }
+
+    #[test]
+    fn stops_at_syntax_error() {
+        let results = decode("{\"id\": 1} {oops} {\"id\": 2}");
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &Event { id: 1 });
+        assert!(matches!(results[1], Err(CodecError::Json { .. })));
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use wasm_sockets::Message;
+
+use super::{split_version, Codec, CodecError};
+
+/// Encodes values as bincode binary messages, for protocols where both ends
+/// are Rust programs sharing the same type definitions.
+///
+/// Every message is prefixed with a protocol version byte. Messages with a
+/// different version fail to decode with [`CodecError::VersionMismatch`]
+/// rather than producing garbage values.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bincode {
+    /// Protocol version byte written to and expected in every message.
+    pub version: u8,
+}
+
+impl Bincode {
+    /// Creates a codec with the given protocol version byte.
+    pub fn new(version: u8) -> Self {
+        Bincode { version }
+    }
+}
+
+impl Codec for Bincode {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Message, CodecError> {
+        let mut bytes = vec![self.version];
+        bincode::serialize_into(&mut bytes, value).map_err(CodecError::Bincode)?;
+        Ok(Message::Binary(bytes))
+    }
+
+    fn decode<T>(&self, message: &Message) -> Result<T, CodecError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match message {
+            Message::Binary(bytes) => {
+                let payload = split_version(bytes, self.version)?;
+                bincode::deserialize(payload).map_err(CodecError::Bincode)
+            }
+            Message::Text(_) => Err(CodecError::UnexpectedText),
+        }
+    }
+}
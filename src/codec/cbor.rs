@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use wasm_sockets::Message;
+
+use super::{Codec, CodecError};
+
+/// Encodes values as CBOR binary messages.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cbor;
+
+impl Codec for Cbor {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Message, CodecError> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes).map_err(CodecError::CborEncode)?;
+        Ok(Message::Binary(bytes))
+    }
+
+    fn decode<T>(&self, message: &Message) -> Result<T, CodecError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match message {
+            Message::Binary(bytes) => {
+                ciborium::de::from_reader(bytes.as_slice()).map_err(CodecError::CborDecode)
+            }
+            Message::Text(_) => Err(CodecError::UnexpectedText),
+        }
+    }
+}
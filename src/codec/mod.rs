@@ -0,0 +1,338 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_sockets::Message;
+
+#[cfg(feature = "bincode")]
+mod bincode;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+#[cfg(feature = "postcard")]
+mod postcard;
+
+#[cfg(feature = "bincode")]
+pub use self::bincode::Bincode;
+#[cfg(feature = "postcard")]
+pub use self::postcard::Postcard;
+#[cfg(feature = "cbor")]
+pub use cbor::Cbor;
+#[cfg(feature = "msgpack")]
+pub use msgpack::MsgPack;
+
+/// Converts values to and from WebSocket messages.
+///
+/// Use a codec with [`DioxusWs::send_encoded`](crate::DioxusWs::send_encoded)
+/// and [`use_ws_context_provider_with_codec`](crate::use_ws_context_provider_with_codec).
+pub trait Codec {
+    /// Serializes `value` into a message.
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Message, CodecError>;
+
+    /// Deserializes a message into a value.
+    fn decode<T>(&self, message: &Message) -> Result<T, CodecError>
+    where
+        T: for<'de> Deserialize<'de>;
+}
+
+/// Encodes values as JSON messages. This is the default codec.
+///
+/// By default JSON is sent as text messages and binary messages are
+/// rejected. Some servers and proxies send UTF-8 JSON in binary messages
+/// instead, which `accept_binary` allows.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json {
+    /// Parse binary messages as UTF-8 JSON instead of rejecting them.
+    pub accept_binary: bool,
+    /// Send JSON as binary messages instead of text.
+    pub binary_output: bool,
+}
+
+impl Codec for Json {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Message, CodecError> {
+        if self.binary_output {
+            Ok(Message::Binary(serde_json::to_vec(value)?))
+        } else {
+            Ok(Message::Text(serde_json::to_string(value)?))
+        }
+    }
+
+    fn decode<T>(&self, message: &Message) -> Result<T, CodecError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match message {
+            Message::Text(text) => decode_json(&mut serde_json::Deserializer::from_str(text)),
+            Message::Binary(bytes) if self.accept_binary => {
+                decode_json(&mut serde_json::Deserializer::from_slice(bytes))
+            }
+            Message::Binary(_) => Err(CodecError::UnexpectedBinary),
+        }
+    }
+}
+
+// Deserializes a single JSON value, keeping track of where in the document an
+// error occurred.
+fn decode_json<'de, R, T>(de: &mut serde_json::Deserializer<R>) -> Result<T, CodecError>
+where
+    R: serde_json::de::Read<'de>,
+    T: Deserialize<'de>,
+{
+    let value = serde_path_to_error::deserialize(&mut *de).map_err(json_error)?;
+    de.end()?;
+
+    Ok(value)
+}
+
+pub(crate) fn json_error(err: serde_path_to_error::Error<serde_json::Error>) -> CodecError {
+    let path = err.path().to_string();
+    CodecError::Json {
+        path: Some(path),
+        error: err.into_inner(),
+    }
+}
+
+/// Reasons a value could not be encoded or decoded.
+///
+/// Some variants only exist with the matching cargo feature enabled.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CodecError {
+    /// JSON (de)serialization failed. For decoding errors, `path` is the
+    /// location of the offending value, e.g. `orders[3].price`.
+    Json {
+        path: Option<String>,
+        error: serde_json::Error,
+    },
+    /// A binary message was received by a codec that only handles text.
+    UnexpectedBinary,
+    /// A text message was received by a codec that only handles binary.
+    UnexpectedText,
+    /// MessagePack serialization failed.
+    #[cfg(feature = "msgpack")]
+    MsgPackEncode(rmp_serde::encode::Error),
+    /// MessagePack deserialization failed.
+    #[cfg(feature = "msgpack")]
+    MsgPackDecode(rmp_serde::decode::Error),
+    /// A versioned binary message carried a different protocol version, or
+    /// no version byte at all.
+    VersionMismatch { expected: u8, found: Option<u8> },
+    /// postcard (de)serialization failed.
+    #[cfg(feature = "postcard")]
+    Postcard(::postcard::Error),
+    /// bincode (de)serialization failed.
+    #[cfg(feature = "bincode")]
+    Bincode(::bincode::Error),
+    /// simd-json deserialization failed.
+    #[cfg(feature = "simd-json")]
+    SimdJson(simd_json::Error),
+    /// A compressed binary message could not be decompressed.
+    #[cfg(feature = "compression")]
+    Decompression(String),
+    /// protobuf decoding failed.
+    #[cfg(feature = "protobuf")]
+    Protobuf(prost::DecodeError),
+    /// CBOR serialization failed.
+    #[cfg(feature = "cbor")]
+    CborEncode(ciborium::ser::Error<std::io::Error>),
+    /// CBOR deserialization failed.
+    #[cfg(feature = "cbor")]
+    CborDecode(ciborium::de::Error<std::io::Error>),
+    /// An error from a codec outside this crate.
+    Custom(Box<dyn std::error::Error>),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Json {
+                path: Some(path),
+                error,
+            } => write!(f, "JSON error at {}: {}", path, error),
+            CodecError::Json { path: None, error } => write!(f, "JSON error: {}", error),
+            CodecError::UnexpectedBinary => write!(f, "binary websocket message unsupported"),
+            CodecError::UnexpectedText => write!(f, "text websocket message unsupported"),
+            #[cfg(feature = "msgpack")]
+            CodecError::MsgPackEncode(err) => write!(f, "MessagePack error: {}", err),
+            #[cfg(feature = "msgpack")]
+            CodecError::MsgPackDecode(err) => write!(f, "MessagePack error: {}", err),
+            CodecError::VersionMismatch {
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "protocol version mismatch: expected {}, found {}",
+                expected, found
+            ),
+            CodecError::VersionMismatch {
+                expected,
+                found: None,
+            } => write!(
+                f,
+                "protocol version mismatch: expected {}, found empty message",
+                expected
+            ),
+            #[cfg(feature = "postcard")]
+            CodecError::Postcard(err) => write!(f, "postcard error: {}", err),
+            #[cfg(feature = "bincode")]
+            CodecError::Bincode(err) => write!(f, "bincode error: {}", err),
+            #[cfg(feature = "simd-json")]
+            CodecError::SimdJson(err) => write!(f, "JSON error: {}", err),
+            #[cfg(feature = "compression")]
+            CodecError::Decompression(err) => write!(f, "decompression failed: {}", err),
+            #[cfg(feature = "protobuf")]
+            CodecError::Protobuf(err) => write!(f, "protobuf error: {}", err),
+            #[cfg(feature = "cbor")]
+            CodecError::CborEncode(err) => write!(f, "CBOR error: {}", err),
+            #[cfg(feature = "cbor")]
+            CodecError::CborDecode(err) => write!(f, "CBOR error: {}", err),
+            CodecError::Custom(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl CodecError {
+    /// Returns the location of the value that failed to decode, if known.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            CodecError::Json { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(value: serde_json::Error) -> Self {
+        CodecError::Json {
+            path: None,
+            error: value,
+        }
+    }
+}
+
+// Checks the protocol version byte at the start of `bytes` and returns the
+// rest of the message.
+#[cfg(any(feature = "postcard", feature = "bincode"))]
+fn split_version(bytes: &[u8], expected: u8) -> Result<&[u8], CodecError> {
+    match bytes.split_first() {
+        Some((&found, payload)) if found == expected => Ok(payload),
+        Some((&found, _)) => Err(CodecError::VersionMismatch {
+            expected,
+            found: Some(found),
+        }),
+        None => Err(CodecError::VersionMismatch {
+            expected,
+            found: None,
+        }),
+    }
+}
+
+// Run natively, see the README.
+#[cfg(all(
+    test,
+    any(
+        feature = "msgpack",
+        feature = "cbor",
+        feature = "postcard",
+        feature = "bincode"
+    )
+))]
+mod tests {
+    use super::*;
+
+    // The enums from examples/json.rs.
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    enum WsResponse {
+        A,
+        B,
+        C,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    enum WsRequest {
+        A,
+        B,
+        C,
+    }
+
+    // Checks that a binary codec round-trips the example enums and rejects
+    // text messages.
+    fn round_trip<C: Codec>(codec: C) {
+        for request in [WsRequest::A, WsRequest::B, WsRequest::C] {
+            let msg = codec.encode(&request).unwrap();
+            assert!(matches!(msg, Message::Binary(_)));
+            assert_eq!(codec.decode::<WsRequest>(&msg).unwrap(), request);
+        }
+
+        // The echo server in the example sends requests back as responses.
+        let msg = codec.encode(&WsRequest::B).unwrap();
+        assert_eq!(codec.decode::<WsResponse>(&msg).unwrap(), WsResponse::B);
+
+        let msg = Message::Text(String::from("\"A\""));
+        assert!(matches!(
+            codec.decode::<WsResponse>(&msg),
+            Err(CodecError::UnexpectedText)
+        ));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_round_trip() {
+        round_trip(MsgPack);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_round_trip() {
+        round_trip(Cbor);
+    }
+
+    // Checks that a versioned codec rejects messages from another version and
+    // messages without a version byte. `codec` uses version 3, `other` 4.
+    #[cfg(any(feature = "postcard", feature = "bincode"))]
+    fn rejects_other_versions<C: Codec>(codec: C, other: C) {
+        let msg = other.encode(&WsRequest::A).unwrap();
+        assert!(matches!(
+            codec.decode::<WsRequest>(&msg),
+            Err(CodecError::VersionMismatch {
+                expected: 3,
+                found: Some(4)
+            })
+        ));
+
+        let msg = Message::Binary(Vec::new());
+        assert!(matches!(
+            codec.decode::<WsRequest>(&msg),
+            Err(CodecError::VersionMismatch {
+                expected: 3,
+                found: None
+            })
+        ));
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_round_trip() {
+        round_trip(Postcard::new(3));
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_rejects_other_versions() {
+        rejects_other_versions(Postcard::new(3), Postcard::new(4));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_round_trip() {
+        round_trip(Bincode::new(3));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_rejects_other_versions() {
+        rejects_other_versions(Bincode::new(3), Bincode::new(4));
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use wasm_sockets::Message;
+
+use super::{Codec, CodecError};
+
+/// Encodes values as MessagePack binary messages. Structs are encoded as maps
+/// with field names, so both sides may add optional fields independently.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MsgPack;
+
+impl Codec for MsgPack {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Message, CodecError> {
+        rmp_serde::to_vec_named(value)
+            .map(Message::Binary)
+            .map_err(CodecError::MsgPackEncode)
+    }
+
+    fn decode<T>(&self, message: &Message) -> Result<T, CodecError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match message {
+            Message::Binary(bytes) => {
+                rmp_serde::from_slice(bytes).map_err(CodecError::MsgPackDecode)
+            }
+            Message::Text(_) => Err(CodecError::UnexpectedText),
+        }
+    }
+}
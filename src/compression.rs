@@ -0,0 +1,172 @@
+use wasm_sockets::Message;
+
+use crate::CodecError;
+
+// Header bytes that start every binary message when compression is enabled.
+const RAW: u8 = 0;
+const DEFLATE: u8 = 1;
+
+/// Application-level compression of binary messages, for payloads too large
+/// to send as-is when the browser doesn't negotiate permessage-deflate.
+///
+/// When enabled, every binary message starts with a header byte that tells
+/// whether the rest is deflate-compressed, so both ends must enable it. Text
+/// messages are left untouched.
+#[derive(Clone, Debug)]
+pub struct CompressionConfig {
+    /// Binary messages smaller than this many bytes are sent uncompressed.
+    pub threshold: usize,
+    /// Deflate level, from 0 (fastest) to 10 (smallest).
+    pub level: u8,
+    /// Incoming messages that decompress to more than this many bytes are
+    /// rejected.
+    pub max_decompressed_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            threshold: 1024,
+            level: 6,
+            max_decompressed_size: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Adds the header byte to an outgoing message, compressing it if it's
+    /// large enough and compression actually makes it smaller.
+    pub(crate) fn compress(&self, message: Message) -> Message {
+        let bytes = match message {
+            Message::Binary(bytes) => bytes,
+            text => return text,
+        };
+
+        if bytes.len() >= self.threshold {
+            let compressed = miniz_oxide::deflate::compress_to_vec(&bytes, self.level);
+            if compressed.len() < bytes.len() {
+                return Message::Binary(with_header(DEFLATE, &compressed));
+            }
+        }
+
+        Message::Binary(with_header(RAW, &bytes))
+    }
+
+    /// Strips the header byte from an incoming message, decompressing it if
+    /// needed. Returns `None` for text messages, which pass through as-is.
+    pub(crate) fn decompress(&self, message: &Message) -> Result<Option<Message>, CodecError> {
+        let bytes = match message {
+            Message::Binary(bytes) => bytes,
+            Message::Text(_) => return Ok(None),
+        };
+
+        let bytes = match bytes.split_first() {
+            Some((&RAW, payload)) => payload.to_vec(),
+            Some((&DEFLATE, payload)) => miniz_oxide::inflate::decompress_to_vec_with_limit(
+                payload,
+                self.max_decompressed_size,
+            )
+            .map_err(|err| CodecError::Decompression(format!("{:?}", err)))?,
+            Some((header, _)) => {
+                return Err(CodecError::Decompression(format!(
+                    "unknown compression header {}",
+                    header
+                )))
+            }
+            None => {
+                return Err(CodecError::Decompression(String::from(
+                    "missing compression header",
+                )))
+            }
+        };
+
+        Ok(Some(Message::Binary(bytes)))
+    }
+}
+
+fn with_header(header: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(payload.len() + 1);
+    bytes.push(header);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+// Run natively, see the README.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(message: Message) -> Vec<u8> {
+        match message {
+            Message::Binary(bytes) => bytes,
+            Message::Text(_) => panic!("expected a binary message"),
+        }
+    }
+
+    fn decompressed(config: &CompressionConfig, message: Message) -> Vec<u8> {
+        bytes(config.decompress(&message).unwrap().unwrap())
+    }
+
+    #[test]
+    fn small_payloads_stay_raw() {
+        let config = CompressionConfig::default();
+        let payload = vec![7; config.threshold - 1];
+
+        let sent = bytes(config.compress(Message::Binary(payload.clone())));
+        assert_eq!(sent[0], RAW);
+        assert_eq!(decompressed(&config, Message::Binary(sent)), payload);
+    }
+
+    #[test]
+    fn large_payloads_round_trip() {
+        let config = CompressionConfig::default();
+        let payload = b"hello websocket ".repeat(1024);
+
+        let sent = bytes(config.compress(Message::Binary(payload.clone())));
+        assert_eq!(sent[0], DEFLATE);
+        assert!(sent.len() < payload.len());
+        assert_eq!(decompressed(&config, Message::Binary(sent)), payload);
+    }
+
+    #[test]
+    fn incompressible_payloads_stay_raw() {
+        let config = CompressionConfig::default();
+        // xorshift noise, which deflate can't shrink.
+        let mut state = 0x2545_f491_u32;
+        let payload: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+
+        let sent = bytes(config.compress(Message::Binary(payload.clone())));
+        assert_eq!(sent[0], RAW);
+        assert_eq!(decompressed(&config, Message::Binary(sent)), payload);
+    }
+
+    #[test]
+    fn rejects_unknown_and_missing_headers() {
+        let config = CompressionConfig::default();
+
+        let unknown = config.decompress(&Message::Binary(vec![9, 1, 2, 3]));
+        assert!(matches!(unknown, Err(CodecError::Decompression(_))));
+
+        let missing = config.decompress(&Message::Binary(Vec::new()));
+        assert!(matches!(missing, Err(CodecError::Decompression(_))));
+    }
+
+    #[test]
+    fn enforces_max_decompressed_size() {
+        let config = CompressionConfig {
+            max_decompressed_size: 1024,
+            ..Default::default()
+        };
+        let sent = config.compress(Message::Binary(vec![0; 64 * 1024]));
+
+        let result = config.decompress(&sent);
+        assert!(matches!(result, Err(CodecError::Decompression(_))));
+    }
+}
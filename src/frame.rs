@@ -0,0 +1,127 @@
+use dioxus::prelude::*;
+use serde::Deserialize;
+use wasm_sockets::Message;
+
+use crate::{use_provider, CodecError, WsConfig, WsInner};
+
+/// A borrowed view of an incoming message, see
+/// [`use_ws_context_provider_borrowed`].
+///
+/// Decoding from a frame lets message types borrow their string and byte
+/// fields from the message instead of allocating a copy of each one.
+pub struct Frame<'a> {
+    bytes: &'a mut [u8],
+    text: bool,
+}
+
+impl<'a> Frame<'a> {
+    // Wraps the payload of a message. `text` tells whether it arrived as a
+    // text message.
+    pub(crate) fn new(bytes: &'a mut [u8], text: bool) -> Self {
+        Frame { bytes, text }
+    }
+
+    /// Returns true if the frame arrived as a text message.
+    pub fn is_text(&self) -> bool {
+        self.text
+    }
+
+    /// Returns the payload.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes
+    }
+
+    /// Returns the payload as a string, or `None` if it isn't valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.bytes).ok()
+    }
+
+    /// Deserializes the payload as JSON. The result may borrow from the
+    /// frame, e.g. `&'a str` fields (or `Cow<'a, str>` with
+    /// `#[serde(borrow)]` for strings that may contain escapes).
+    ///
+    /// Unlike [`Json`](crate::Json), errors don't carry the path of the
+    /// offending value, since tracking it allocates.
+    pub fn json<T: Deserialize<'a>>(self) -> Result<T, CodecError> {
+        let bytes: &'a [u8] = self.bytes;
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Deserializes the payload as JSON with simd-json, which parses in place
+    /// and so consumes the frame.
+    #[cfg(feature = "simd-json")]
+    pub fn simd_json<T: Deserialize<'a>>(self) -> Result<T, CodecError> {
+        simd_json::serde::from_slice(self.bytes).map_err(CodecError::SimdJson)
+    }
+}
+
+/// Provide websocket context with a handler that receives each incoming
+/// message as a borrowed [`Frame`], for hot paths where allocating owned
+/// messages is too slow.
+///
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct Trade<'a> {
+///     symbol: &'a str,
+///     price: f64,
+/// }
+///
+/// use_ws_context_provider_borrowed(&cx, url, move |frame| {
+///     match frame.json::<Trade>() {
+///         Ok(trade) => record(trade.symbol, trade.price),
+///         Err(e) => log::error!("Bad trade: {}", e),
+///     }
+/// });
+/// ```
+pub fn use_ws_context_provider_borrowed(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl for<'a> Fn(Frame<'a>) + 'static,
+) {
+    use_ws_context_provider_borrowed_tagged::<()>(cx, url, handler)
+}
+
+/// Like [`use_ws_context_provider_borrowed`], using the given connection
+/// options.
+pub fn use_ws_context_provider_borrowed_with_config(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl for<'a> Fn(Frame<'a>) + 'static,
+) {
+    use_ws_context_provider_borrowed_with_config_tagged::<()>(cx, url, config, handler)
+}
+
+/// Like [`use_ws_context_provider_borrowed`], for the connection identified
+/// by `Tag`.
+pub fn use_ws_context_provider_borrowed_tagged<Tag: 'static>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl for<'a> Fn(Frame<'a>) + 'static,
+) {
+    use_ws_context_provider_borrowed_with_config_tagged::<Tag>(
+        cx,
+        url,
+        WsConfig::default(),
+        handler,
+    )
+}
+
+/// Like [`use_ws_context_provider_borrowed_with_config`], for the connection
+/// identified by `Tag`.
+pub fn use_ws_context_provider_borrowed_with_config_tagged<Tag: 'static>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl for<'a> Fn(Frame<'a>) + 'static,
+) {
+    let handler = move |_: &WsInner, msg: Message| {
+        let (mut bytes, text) = match msg {
+            Message::Text(text) => (text.into_bytes(), true),
+            Message::Binary(bytes) => (bytes, false),
+        };
+        handler(Frame::new(&mut bytes, text));
+    };
+
+    use_provider::<Tag>(cx, url, config, handler);
+}
@@ -0,0 +1,130 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+use wasm_sockets::Message;
+
+use crate::{subscribe::use_message_callback, timer, use_ws_context_tagged, Codec};
+
+/// A decoded message together with the time it arrived.
+#[derive(Clone, Debug)]
+pub struct Received<T> {
+    /// The decoded message.
+    pub value: T,
+    /// When the message arrived, in milliseconds since the Unix epoch.
+    pub received_at: f64,
+}
+
+/// The most recent raw messages of a connection, so that components mounting
+/// later can still show them.
+pub(crate) struct MessageHistory {
+    capacity: usize,
+    entries: VecDeque<(f64, Message)>,
+}
+
+impl MessageHistory {
+    pub(crate) fn new(capacity: usize) -> Self {
+        MessageHistory {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Makes room for at least `capacity` messages. The history never
+    /// shrinks, as other components may rely on it.
+    pub(crate) fn reserve(&mut self, capacity: usize) {
+        self.capacity = self.capacity.max(capacity);
+    }
+
+    pub(crate) fn record(&mut self, message: &Message) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((timer::now(), message.clone()));
+    }
+}
+
+/// Returns the last `capacity` incoming messages that decode as JSON into
+/// `T`, oldest first, and re-renders the calling component when a new one
+/// arrives.
+///
+/// Messages are decoded with the connection's [`WsConfig::json`](crate::WsConfig::json)
+/// codec and messages that aren't a valid `T` are skipped. The connection
+/// keeps the raw messages for the largest `capacity` requested so far (or
+/// [`WsConfig::history`](crate::WsConfig::history), if larger), so a
+/// component mounting later starts out with the messages received before it.
+///
+/// Returns a snapshot of the history. It is only cloned when a message
+/// arrives while an earlier snapshot is still held, e.g. by an event handler.
+pub fn use_ws_history<T>(cx: &ScopeState, capacity: usize) -> Rc<VecDeque<Received<T>>>
+where
+    T: for<'de> Deserialize<'de> + Clone + 'static,
+{
+    use_ws_history_tagged::<(), T>(cx, capacity)
+}
+
+/// Like [`use_ws_history`], for the connection identified by `Tag`.
+pub fn use_ws_history_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    capacity: usize,
+) -> Rc<VecDeque<Received<T>>>
+where
+    T: for<'de> Deserialize<'de> + Clone + 'static,
+{
+    let ws = use_ws_context_tagged::<Tag>(cx);
+    let json = ws.inner.config.json;
+
+    let history: Rc<RefCell<Rc<VecDeque<Received<T>>>>> = cx
+        .use_hook(|| {
+            let mut shared = ws.inner.history.borrow_mut();
+            shared.reserve(capacity);
+
+            let mut history = VecDeque::with_capacity(capacity);
+            for (received_at, msg) in &shared.entries {
+                if let Ok(value) = json.decode(msg) {
+                    push_bounded(
+                        &mut history,
+                        capacity,
+                        Received {
+                            value,
+                            received_at: *received_at,
+                        },
+                    );
+                }
+            }
+            Rc::new(RefCell::new(Rc::new(history)))
+        })
+        .clone();
+    let update = cx.use_hook(|| cx.schedule_update()).clone();
+
+    {
+        let history = history.clone();
+        use_message_callback(cx, &ws, move |msg| {
+            if let Ok(value) = json.decode(msg) {
+                let received = Received {
+                    value,
+                    received_at: timer::now(),
+                };
+                let mut history = history.borrow_mut();
+                push_bounded(Rc::make_mut(&mut history), capacity, received);
+                update();
+            }
+        });
+    }
+
+    let snapshot = history.borrow().clone();
+    snapshot
+}
+
+fn push_bounded<T>(history: &mut VecDeque<T>, capacity: usize, item: T) {
+    if capacity == 0 {
+        return;
+    }
+    if history.len() == capacity {
+        history.pop_front();
+    }
+    history.push_back(item);
+}
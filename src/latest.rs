@@ -0,0 +1,63 @@
+use std::{cell::RefCell, rc::Rc};
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+use wasm_sockets::Message;
+
+use crate::{subscribe::use_message_callback, use_ws_context_tagged, Codec};
+
+/// Returns the latest incoming message that decodes as JSON into `T`, or
+/// `None` until one arrives. The calling component is re-rendered whenever a
+/// new one does.
+///
+/// Messages are decoded with the connection's [`WsConfig::json`](crate::WsConfig::json)
+/// codec. Messages that aren't a valid `T` are skipped.
+pub fn use_ws_latest<T>(cx: &ScopeState) -> Option<Rc<T>>
+where
+    T: for<'de> Deserialize<'de> + 'static,
+{
+    use_ws_latest_tagged::<(), T>(cx)
+}
+
+/// Like [`use_ws_latest`], for the connection identified by `Tag`.
+pub fn use_ws_latest_tagged<Tag: 'static, T>(cx: &ScopeState) -> Option<Rc<T>>
+where
+    T: for<'de> Deserialize<'de> + 'static,
+{
+    let json = use_ws_context_tagged::<Tag>(cx).inner.config.json;
+
+    use_ws_latest_with_tagged::<Tag, T>(cx, move |msg| json.decode(msg).ok())
+}
+
+/// Returns the latest value produced by `map` from an incoming message, or
+/// `None` until one is. Messages for which `map` returns `None` are skipped.
+/// The calling component is re-rendered whenever a new value is produced.
+pub fn use_ws_latest_with<T: 'static>(
+    cx: &ScopeState,
+    map: impl Fn(&Message) -> Option<T> + 'static,
+) -> Option<Rc<T>> {
+    use_ws_latest_with_tagged::<(), T>(cx, map)
+}
+
+/// Like [`use_ws_latest_with`], for the connection identified by `Tag`.
+pub fn use_ws_latest_with_tagged<Tag: 'static, T: 'static>(
+    cx: &ScopeState,
+    map: impl Fn(&Message) -> Option<T> + 'static,
+) -> Option<Rc<T>> {
+    let ws = use_ws_context_tagged::<Tag>(cx);
+    let latest = cx.use_hook(|| Rc::new(RefCell::new(None::<Rc<T>>))).clone();
+    let update = cx.use_hook(|| cx.schedule_update()).clone();
+
+    {
+        let latest = latest.clone();
+        use_message_callback(cx, &ws, move |msg| {
+            if let Some(value) = map(msg) {
+                latest.replace(Some(Rc::new(value)));
+                update();
+            }
+        });
+    }
+
+    let value = latest.borrow().clone();
+    value
+}
@@ -0,0 +1,28 @@
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+/// A message that decodes as `T` if possible, and otherwise as its raw JSON
+/// value.
+///
+/// Use this as the message type of a provider to keep older clients working
+/// when the server adds, say, a new enum variant: well-formed messages that
+/// aren't a valid `T` end up in `Fallback` instead of failing to decode.
+/// Messages that aren't well-formed still fail. Works with self-describing
+/// codecs such as [`Json`](crate::Json).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lenient<T> {
+    /// The message is a valid `T`.
+    Known(T),
+    /// The message is well-formed, but not a valid `T`.
+    Fallback(serde_json::Value),
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        match T::deserialize(&value) {
+            Ok(known) => Ok(Lenient::Known(known)),
+            Err(_) => Ok(Lenient::Fallback(value)),
+        }
+    }
+}
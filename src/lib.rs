@@ -1,85 +1,687 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    rc::{Rc, Weak},
+    task::Waker,
+};
 
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsValue;
 use wasm_sockets::{EventClient, Message, WebSocketError};
 
-#[derive(Clone)]
-pub struct DioxusWs {
-    event_client: Rc<RefCell<EventClient>>,
-    connected: Rc<RefCell<bool>>,
+mod batch;
+mod codec;
+#[cfg(feature = "compression")]
+mod compression;
+mod frame;
+mod history;
+mod latest;
+mod lenient;
+#[cfg(feature = "protobuf")]
+mod proto;
+mod queue;
+mod reconnect;
+mod reducer;
+mod status;
+mod stream;
+mod subscribe;
+mod subscribers;
+mod timer;
+mod typed;
+
+pub use batch::{
+    use_ws_context_provider_json_batch, use_ws_context_provider_json_batch_tagged,
+    use_ws_context_provider_json_batch_with_config,
+    use_ws_context_provider_json_batch_with_config_tagged,
+};
+#[cfg(feature = "bincode")]
+pub use codec::Bincode;
+#[cfg(feature = "cbor")]
+pub use codec::Cbor;
+#[cfg(feature = "msgpack")]
+pub use codec::MsgPack;
+#[cfg(feature = "postcard")]
+pub use codec::Postcard;
+pub use codec::{Codec, CodecError, Json};
+#[cfg(feature = "compression")]
+pub use compression::CompressionConfig;
+pub use frame::{
+    use_ws_context_provider_borrowed, use_ws_context_provider_borrowed_tagged,
+    use_ws_context_provider_borrowed_with_config,
+    use_ws_context_provider_borrowed_with_config_tagged, Frame,
+};
+pub use history::{use_ws_history, use_ws_history_tagged, Received};
+pub use latest::{
+    use_ws_latest, use_ws_latest_tagged, use_ws_latest_with, use_ws_latest_with_tagged,
+};
+pub use lenient::Lenient;
+#[cfg(feature = "protobuf")]
+pub use proto::{
+    use_ws_context_provider_proto, use_ws_context_provider_proto_any,
+    use_ws_context_provider_proto_framed, use_ws_context_provider_proto_tagged,
+    use_ws_context_provider_proto_with_config, use_ws_context_provider_proto_with_config_tagged,
+    ProtoFraming,
+};
+pub use queue::{OverflowPolicy, QueueConfig};
+pub use reconnect::ReconnectConfig;
+pub use reducer::{use_ws_reducer, use_ws_reducer_tagged};
+pub use status::ConnectionState;
+pub use stream::{WsReceiver, WsSender};
+pub use subscribe::{use_ws_subscribe, use_ws_subscribe_tagged};
+pub use typed::{
+    try_use_typed_ws_context, try_use_typed_ws_context_tagged, use_typed_ws_context,
+    use_typed_ws_context_provider, use_typed_ws_context_provider_tagged,
+    use_typed_ws_context_provider_with_config, use_typed_ws_context_provider_with_config_tagged,
+    use_typed_ws_context_tagged, TypedWs,
+};
+
+use history::MessageHistory;
+use queue::OutboundQueue;
+use subscribers::{Subscribers, Subscription};
+use timer::Timeout;
+
+// Handles incoming messages for a connection.
+type Handler = Rc<dyn Fn(&WsInner, Message)>;
+
+// Reports incoming messages that could not be decoded.
+type DecodeErrorHandler = Rc<dyn Fn(&DecodeFailure)>;
+
+// Close code for a normal closure, see RFC 6455 section 7.4.1.
+const NORMAL_CLOSURE: u16 = 1000;
+
+/// Connection options for [`DioxusWs`].
+#[derive(Clone, Default)]
+pub struct WsConfig {
+    /// How to reconnect after the socket closes.
+    pub reconnect: ReconnectConfig,
+    /// How to buffer messages sent while the socket isn't open.
+    pub queue: QueueConfig,
+    /// The JSON codec used by `send_json` and by every hook that decodes
+    /// JSON on this connection, e.g. to send and accept JSON in binary
+    /// messages.
+    pub json: Json,
+    /// Called whenever an incoming message fails to decode.
+    pub on_decode_error: Option<DecodeErrorHandler>,
+    /// Compresses large binary messages. Both ends must agree on this.
+    #[cfg(feature = "compression")]
+    pub compression: Option<CompressionConfig>,
+    /// Number of recent messages to keep for [`use_ws_history`] from the
+    /// start, before any component asks for them.
+    pub history: usize,
+}
+
+/// An incoming message that could not be decoded, see
+/// [`WsConfig::on_decode_error`].
+#[derive(Debug)]
+pub struct DecodeFailure {
+    /// The raw message as received.
+    pub message: Message,
+    /// Why decoding failed. For JSON, [`CodecError::path`] points at the
+    /// offending value.
+    pub error: CodecError,
+    /// The url of the connection the message arrived on.
+    pub url: String,
+    /// When the message arrived, in milliseconds since the Unix epoch.
+    pub received_at: f64,
+    /// The number of decode errors on this connection so far, including
+    /// this one.
+    pub count: u64,
+}
+
+/// A handle to a WebSocket connection.
+///
+/// `Tag` is a marker type that tells several connections in the same
+/// component tree apart, see [`use_ws_context_provider_tagged`] and
+/// [`use_ws_context_tagged`]. It defaults to `()`.
+pub struct DioxusWs<Tag = ()> {
+    inner: Rc<WsInner>,
+    _tag: PhantomData<fn() -> Tag>,
 }
 
+impl<Tag> Clone for DioxusWs<Tag> {
+    fn clone(&self) -> Self {
+        DioxusWs {
+            inner: self.inner.clone(),
+            _tag: PhantomData,
+        }
+    }
+}
+
+struct WsInner {
+    url: RefCell<String>,
+    config: WsConfig,
+    event_client: RefCell<Option<EventClient>>,
+    status: RefCell<ConnectionState>,
+    status_listeners: Subscribers<ConnectionState>,
+    handler: RefCell<Option<Handler>>,
+    subscribers: Subscribers<Message>,
+    decode_errors: Cell<u64>,
+    history: RefCell<MessageHistory>,
+    queue: RefCell<OutboundQueue>,
+    // Sinks waiting for room in the queue. Woken on every status change.
+    send_wakers: RefCell<Vec<Waker>>,
+    // Incremented for every new EventClient, so that events from replaced
+    // clients can be ignored.
+    generation: Cell<u64>,
+    // Set by `close`, so that the resulting close event doesn't reconnect.
+    closed_by_user: Cell<bool>,
+    // Set once the provider unmounts. The connection is never reopened.
+    shut_down: Cell<bool>,
+    // Set once reconnecting stops for good, until `reconnect` is called.
+    gave_up: Cell<bool>,
+    attempt: Cell<u32>,
+    retry_timer: RefCell<Option<Timeout>>,
+}
+
+/// Reasons a message could not be sent.
+#[derive(Debug)]
 pub enum SendError {
+    /// The socket isn't open, and either queueing is disabled or the
+    /// connection won't reopen without [`DioxusWs::reconnect`].
     SocketNotConnected(),
+    /// The browser refused to send the message.
     JsError(JsValue),
+    /// The value could not be serialized.
+    Serialization(CodecError),
+    /// The socket isn't open and the outbound queue rejected the message.
+    QueueFull,
+    /// The connection was shut down because its provider unmounted.
+    ConnectionClosed,
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::SocketNotConnected() => write!(f, "WebSocket is not connected"),
+            SendError::JsError(err) => write!(f, "JavaScript error: {:?}", err),
+            SendError::Serialization(err) => write!(f, "Serialization failed: {}", err),
+            SendError::QueueFull => write!(f, "WebSocket send queue is full"),
+            SendError::ConnectionClosed => write!(f, "WebSocket connection was shut down"),
+        }
+    }
 }
 
+impl std::error::Error for SendError {}
+
 impl From<JsValue> for SendError {
     fn from(value: JsValue) -> Self {
         SendError::JsError(value)
     }
 }
 
+impl From<CodecError> for SendError {
+    fn from(value: CodecError) -> Self {
+        SendError::Serialization(value)
+    }
+}
+
+/// What happened to a message that was sent successfully.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// The message was handed to the socket.
+    Sent,
+    /// The socket isn't open, the message will be sent once it is.
+    Queued,
+}
+
 impl DioxusWs {
+    /// Connects to `url` with the default connection options.
     pub fn new(url: &str) -> Result<DioxusWs, WebSocketError> {
-        let mut client = wasm_sockets::EventClient::new(url)?;
+        DioxusWs::with_config(url, WsConfig::default())
+    }
 
-        let connected = Rc::new(RefCell::new(false));
+    /// Like [`DioxusWs::new`], with custom connection options.
+    pub fn with_config(url: &str, config: WsConfig) -> Result<DioxusWs, WebSocketError> {
+        let ws = DioxusWs::unconnected(url, config);
+        ws.inner.connect()?;
 
-        client.set_on_error(Some(Box::new(|error| {
-            log::error!("Web socket error: {:?}", error);
-        })));
+        Ok(ws)
+    }
+}
+
+impl<Tag> DioxusWs<Tag> {
+    // Creates a handle without opening a socket yet.
+    fn unconnected(url: &str, config: WsConfig) -> DioxusWs<Tag> {
+        let history = MessageHistory::new(config.history);
+        let inner = Rc::new(WsInner {
+            url: RefCell::new(url.to_string()),
+            config,
+            event_client: RefCell::new(None),
+            status: RefCell::new(ConnectionState::Connecting),
+            status_listeners: Subscribers::new(),
+            handler: RefCell::new(None),
+            subscribers: Subscribers::new(),
+            decode_errors: Cell::new(0),
+            history: RefCell::new(history),
+            queue: RefCell::new(OutboundQueue::default()),
+            send_wakers: RefCell::new(Vec::new()),
+            generation: Cell::new(0),
+            closed_by_user: Cell::new(false),
+            shut_down: Cell::new(false),
+            gave_up: Cell::new(false),
+            attempt: Cell::new(0),
+            retry_timer: RefCell::new(None),
+        });
+
+        DioxusWs {
+            inner,
+            _tag: PhantomData,
+        }
+    }
+
+    // Sets the handler for incoming messages. The handler survives reconnects.
+    fn set_handler(&self, handler: Handler) {
+        self.inner.handler.replace(Some(handler));
+    }
+
+    /// Returns the url the connection uses.
+    pub fn url(&self) -> String {
+        self.inner.url.borrow().clone()
+    }
+
+    /// Closes the connection with the given close code and reason. The
+    /// connection stays closed until [`DioxusWs::reconnect`] is called.
+    ///
+    /// Fails if the browser rejects the code or reason, see
+    /// [`WebSocket.close()`](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close).
+    pub fn close(&self, code: u16, reason: &str) -> Result<(), JsValue> {
+        let inner = &self.inner;
+        if inner.shut_down.get() {
+            return Ok(());
+        }
+
+        let live = matches!(
+            *inner.status.borrow(),
+            ConnectionState::Connecting | ConnectionState::Open
+        );
+        if live {
+            if let Some(client) = inner.event_client.borrow().as_ref() {
+                client.close_with_reason(code, reason)?;
+            }
+        }
+
+        inner.closed_by_user.set(true);
+        inner.retry_timer.take();
+
+        if live {
+            inner.set_status(ConnectionState::Closing);
+        } else {
+            inner.set_status(ConnectionState::Closed {
+                code,
+                reason: reason.to_string(),
+                was_clean: true,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Drops the current socket, if any, and connects again right away.
+    /// Also resumes a connection that was closed with [`DioxusWs::close`] or
+    /// that gave up reconnecting.
+    pub fn reconnect(&self) -> Result<(), WebSocketError> {
+        let inner = &self.inner;
+        if inner.shut_down.get() {
+            return Ok(());
+        }
+
+        inner.closed_by_user.set(false);
+        inner.gave_up.set(false);
+        inner.attempt.set(0);
+        inner.retry_timer.take();
+        inner.close_client(NORMAL_CLOSURE);
+
+        inner.connect().map_err(|err| {
+            log::error!("Error reconnecting WebSocket: {}", err);
+            inner.gave_up.set(true);
+            inner.set_status(ConnectionState::Failed(err.to_string()));
+            err
+        })
+    }
+
+    /// Points the connection at a new url and reconnects.
+    pub fn set_url(&self, url: &str) -> Result<(), WebSocketError> {
+        self.inner.url.replace(url.to_string());
+        self.reconnect()
+    }
+
+    /// Returns the number of incoming messages that failed to decode on this
+    /// connection.
+    pub fn decode_error_count(&self) -> u64 {
+        self.inner.decode_errors.get()
+    }
+
+    /// Returns the current state of the connection.
+    pub fn status(&self) -> ConnectionState {
+        self.inner.status.borrow().clone()
+    }
+
+    /// Returns true if the socket is open.
+    pub fn is_connected(&self) -> bool {
+        self.inner.status.borrow().is_open()
+    }
+
+    /// Sends a Message. If the socket isn't open yet, the message is queued
+    /// and sent once the connection opens.
+    pub fn send(&self, msg: Message) -> Result<SendOutcome, SendError> {
+        if self.inner.shut_down.get() {
+            return Err(SendError::ConnectionClosed);
+        }
+        // Queued messages would only wait for a connection that isn't coming.
+        if self.inner.closed_by_user.get() || self.inner.gave_up.get() {
+            return Err(SendError::SocketNotConnected());
+        }
+
+        if self.is_connected() {
+            self.inner.send_now(msg)?;
+            return Ok(SendOutcome::Sent);
+        }
+
+        let config = &self.inner.config.queue;
+        match self.inner.queue.borrow_mut().push(config, msg) {
+            Ok(()) => Ok(SendOutcome::Queued),
+            Err(_) if config.capacity == 0 => Err(SendError::SocketNotConnected()),
+            Err(_) => Err(SendError::QueueFull),
+        }
+    }
+
+    /// Sends a plaintext string.
+    pub fn send_text(&self, text: String) -> Result<SendOutcome, SendError> {
+        let msg = Message::Text(text);
+        self.send(msg)
+    }
+
+    /// Sends data that implements Serialize as JSON.
+    pub fn send_json<T: Serialize>(&self, value: &T) -> Result<SendOutcome, SendError> {
+        self.send_encoded(&self.inner.config.json, value)
+    }
+
+    /// Sends data that implements Serialize as a MessagePack binary message.
+    #[cfg(feature = "msgpack")]
+    pub fn send_msgpack<T: Serialize>(&self, value: &T) -> Result<SendOutcome, SendError> {
+        self.send_encoded(&MsgPack, value)
+    }
+
+    /// Sends data that implements Serialize as a CBOR binary message.
+    #[cfg(feature = "cbor")]
+    pub fn send_cbor<T: Serialize>(&self, value: &T) -> Result<SendOutcome, SendError> {
+        self.send_encoded(&Cbor, value)
+    }
+
+    /// Sends data that implements Serialize, encoded with the given codec.
+    pub fn send_encoded<C: Codec, T: Serialize + ?Sized>(
+        &self,
+        codec: &C,
+        value: &T,
+    ) -> Result<SendOutcome, SendError> {
+        let msg = codec.encode(value)?;
+        self.send(msg)
+    }
+}
+
+impl WsInner {
+    // Opens a new EventClient for the current url, replacing any previous one.
+    fn connect(self: &Rc<Self>) -> Result<(), WebSocketError> {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        self.set_status(ConnectionState::Connecting);
+
+        let mut client = wasm_sockets::EventClient::new(&self.url.borrow())?;
+        let weak = Rc::downgrade(self);
+
+        {
+            let weak = weak.clone();
+            client.set_on_error(Some(Box::new(move |error| {
+                log::error!("Web socket error: {:?}", error);
+                if let Some(inner) = current(&weak, generation) {
+                    inner.set_status(ConnectionState::Failed(format!("{:?}", error)));
+                }
+            })));
+        }
         {
-            let connected = connected.clone();
+            let weak = weak.clone();
             client.set_on_connection(Some(Box::new(move |_client| {
-                let mut borrowed = connected.borrow_mut();
-                *borrowed = true;
+                if let Some(inner) = current(&weak, generation) {
+                    inner.attempt.set(0);
+                    // Flush before announcing the connection, so that queued
+                    // messages go out ahead of anything sent in response.
+                    inner.flush_queue();
+                    inner.set_status(ConnectionState::Open);
+                }
             })));
         }
         {
-            let connected = connected.clone();
-            client.set_on_close(Some(Box::new(move |_client| {
+            let weak = weak.clone();
+            client.set_on_message(Some(Box::new(
+                move |_client: &wasm_sockets::EventClient, message: Message| {
+                    if let Some(inner) = current(&weak, generation) {
+                        inner.receive(message);
+                    }
+                },
+            )));
+        }
+        client.set_on_close(Some(Box::new(move |event| {
+            if let Some(inner) = current(&weak, generation) {
                 log::info!("Websocket connection closed.");
-                let mut borrowed = connected.borrow_mut();
-                *borrowed = false;
-            })));
+                inner.set_status(ConnectionState::Closed {
+                    code: event.code(),
+                    reason: event.reason(),
+                    was_clean: event.was_clean(),
+                });
+                if !inner.closed_by_user.get() {
+                    inner.schedule_reconnect();
+                }
+            }
+        })));
+
+        self.event_client.replace(Some(client));
+
+        Ok(())
+    }
+
+    // Closes the socket for good and drops all callbacks, so nothing keeps
+    // running after the provider is gone.
+    fn shut_down(&self) {
+        if self.shut_down.replace(true) {
+            return;
         }
 
-        Ok(DioxusWs {
-            event_client: Rc::new(RefCell::new(client)),
-            connected,
-        })
+        // Ignore any events still in flight from the current client.
+        self.generation.set(self.generation.get() + 1);
+        self.retry_timer.take();
+        self.handler.take();
+        self.queue.borrow_mut().clear();
+        // Ends any receiver streams.
+        self.subscribers.clear();
+
+        if let Some(client) = self.event_client.borrow_mut().as_mut() {
+            client.set_on_connection(None);
+            client.set_on_message(None);
+            client.set_on_close(None);
+            client.set_on_error(None);
+        }
+        self.close_client(NORMAL_CLOSURE);
+
+        self.set_status(ConnectionState::Closed {
+            code: NORMAL_CLOSURE,
+            reason: String::new(),
+            was_clean: true,
+        });
     }
 
-    // Sends a Message
-    pub fn send(&self, msg: Message) {
-        if *self.connected.borrow() {
-            let result = match msg {
-                Message::Text(text) => self.event_client.borrow().send_string(&text),
-                Message::Binary(binary) => self.event_client.borrow().send_binary(binary),
-            };
-            if let Err(err) = result {
-                log::error!("Error when sending message on websocket: {:?}", err);
+    // Closes the current client. Its callbacks stay attached, since this may
+    // run inside one of them (e.g. `reconnect` from a message handler), but
+    // they are ignored once the generation changes.
+    fn close_client(&self, code: u16) {
+        if let Some(client) = self.event_client.take() {
+            if let Err(err) = client.close_with(code) {
+                log::error!("Error closing WebSocket: {:?}", err);
             }
-        } else {
-            log::error!("WebSocket tried to send message when not connected.");
         }
     }
 
-    // Sends a plaintext string
-    pub fn send_text(&self, text: String) {
-        let msg = Message::Text(text);
-        self.send(msg);
+    // Passes an incoming message to the subscribers and the handler.
+    fn receive(&self, message: Message) {
+        #[cfg(feature = "compression")]
+        let message = match &self.config.compression {
+            Some(compression) => match compression.decompress(&message) {
+                Ok(Some(decompressed)) => decompressed,
+                Ok(None) => message,
+                Err(e) => return self.decode_failed(message, e),
+            },
+            None => message,
+        };
+
+        self.history.borrow_mut().record(&message);
+        self.subscribers.emit(&message);
+
+        let handler = self.handler.borrow().clone();
+        if let Some(handler) = handler {
+            handler(self, message);
+        }
     }
 
-    // Sends data that implements Serialize as JSON
-    pub fn send_json<T: Serialize>(&self, value: &T) {
-        let json = serde_json::to_string(value).unwrap();
-        let msg = Message::Text(json);
-        self.send(msg)
+    fn send_now(&self, msg: Message) -> Result<(), JsValue> {
+        #[cfg(feature = "compression")]
+        let msg = match &self.config.compression {
+            Some(compression) => compression.compress(msg),
+            None => msg,
+        };
+
+        let client = self.event_client.borrow();
+        let client = match client.as_ref() {
+            Some(client) => client,
+            None => return Err(JsValue::from_str("WebSocket client missing")),
+        };
+        match msg {
+            Message::Text(text) => client.send_string(&text),
+            Message::Binary(binary) => client.send_binary(binary),
+        }
     }
+
+    // Sends every queued message, oldest first.
+    fn flush_queue(&self) {
+        let messages = self.queue.borrow_mut().take();
+        for msg in messages {
+            if let Err(err) = self.send_now(msg) {
+                log::error!("Error when sending queued message on websocket: {:?}", err);
+            }
+        }
+    }
+
+    // Counts and reports an incoming message that could not be decoded.
+    fn decode_failed(&self, message: Message, error: CodecError) {
+        let count = self.decode_errors.get() + 1;
+        self.decode_errors.set(count);
+
+        log::error!("Error while deserializing websocket response: {}", error);
+
+        if let Some(on_decode_error) = &self.config.on_decode_error {
+            on_decode_error(&DecodeFailure {
+                message,
+                error,
+                url: self.url.borrow().clone(),
+                received_at: timer::now(),
+                count,
+            });
+        }
+    }
+
+    fn set_status(&self, status: ConnectionState) {
+        self.status.replace(status.clone());
+        self.status_listeners.emit(&status);
+
+        for waker in self.send_wakers.take() {
+            waker.wake();
+        }
+    }
+
+    // Returns true if `send` would have to drop or reject a message because
+    // the queue is full, and the socket may still open to drain it.
+    fn queue_full(&self) -> bool {
+        !self.status.borrow().is_open()
+            && !self.shut_down.get()
+            && !self.closed_by_user.get()
+            && !self.gave_up.get()
+            && self.queue.borrow_mut().is_full(&self.config.queue)
+    }
+
+    fn schedule_reconnect(self: &Rc<Self>) {
+        // Nothing to give up on, keep the close status as it is.
+        if self.config.reconnect.is_disabled() {
+            self.gave_up.set(true);
+            return;
+        }
+
+        let attempt = self.attempt.get();
+        let delay = match self.config.reconnect.delay(attempt) {
+            Some(delay) => delay,
+            None => {
+                log::error!(
+                    "Giving up on websocket {} after {} attempts.",
+                    self.url.borrow(),
+                    attempt
+                );
+                self.gave_up.set(true);
+                self.set_status(ConnectionState::Failed(format!(
+                    "Gave up reconnecting after {} attempts",
+                    attempt
+                )));
+                if let Some(on_give_up) = &self.config.reconnect.on_give_up {
+                    on_give_up();
+                }
+                return;
+            }
+        };
+        self.attempt.set(attempt + 1);
+
+        log::info!("Reconnecting websocket in {:?}.", delay);
+        let weak = Rc::downgrade(self);
+        let retry = Timeout::new(delay, move || {
+            if let Some(inner) = weak.upgrade() {
+                inner.retry_timer.take();
+                if let Err(err) = inner.connect() {
+                    log::error!("Error reconnecting WebSocket: {}", err);
+                    inner.set_status(ConnectionState::Failed(err.to_string()));
+                    inner.schedule_reconnect();
+                }
+            }
+        });
+        if retry.is_none() {
+            self.gave_up.set(true);
+            self.set_status(ConnectionState::Failed(String::from(
+                "Could not schedule reconnection",
+            )));
+            return;
+        }
+        self.retry_timer.replace(retry);
+        self.set_status(ConnectionState::Reconnecting {
+            attempt: attempt + 1,
+            next_retry_at: timer::now() + delay.as_secs_f64() * 1000.0,
+        });
+    }
+}
+
+// Shuts the connection down when the providing component unmounts.
+struct ProviderGuard<Tag> {
+    ws: DioxusWs<Tag>,
+    // The url argument the provider was last rendered with.
+    url: String,
+}
+
+impl<Tag> Drop for ProviderGuard<Tag> {
+    fn drop(&mut self) {
+        self.ws.inner.shut_down();
+    }
+}
+
+// Upgrades `weak` if it still refers to the connection and `generation` is
+// the client that is currently in use.
+fn current(weak: &Weak<WsInner>, generation: u64) -> Option<Rc<WsInner>> {
+    weak.upgrade()
+        .filter(|inner| inner.generation.get() == generation)
 }
 
 fn log_err(s: &str) {
@@ -88,25 +690,114 @@ fn log_err(s: &str) {
 
 /// Provide websocket context with a handler for incoming reqwasm Messages
 pub fn use_ws_context_provider(cx: &ScopeState, url: &str, handler: impl Fn(Message) + 'static) {
-    let handler = Rc::new(handler);
+    use_ws_context_provider_tagged::<()>(cx, url, handler)
+}
+
+/// Provide websocket context with a handler for incoming reqwasm Messages,
+/// using the given connection options.
+pub fn use_ws_context_provider_with_config(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(Message) + 'static,
+) {
+    use_ws_context_provider_with_config_tagged::<()>(cx, url, config, handler)
+}
 
-    cx.use_hook(|| {
-        let ws = DioxusWs::new(url);
-        if let Err(err) = ws {
+/// Provide websocket context with a handler for incoming plaintext messages
+pub fn use_ws_context_provider_text(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(String) + 'static,
+) {
+    use_ws_context_provider_text_tagged::<()>(cx, url, handler)
+}
+
+/// Provide websocket context with a handler for incoming JSON messages.
+/// Note that the message type T must implement Deserialize.
+pub fn use_ws_context_provider_json<T>(cx: &ScopeState, url: &str, handler: impl Fn(T) + 'static)
+where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_tagged::<(), T>(cx, url, handler)
+}
+
+/// Provide websocket context with a handler for incoming JSON messages,
+/// using the given connection options. Messages are decoded with
+/// [`WsConfig::json`].
+pub fn use_ws_context_provider_json_with_config<T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_with_config_tagged::<(), T>(cx, url, config, handler)
+}
+
+/// Like [`use_ws_context_provider`], for the connection identified by `Tag`.
+pub fn use_ws_context_provider_tagged<Tag: 'static>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(Message) + 'static,
+) {
+    use_ws_context_provider_with_config_tagged::<Tag>(cx, url, WsConfig::default(), handler)
+}
+
+/// Like [`use_ws_context_provider_with_config`], for the connection
+/// identified by `Tag`.
+pub fn use_ws_context_provider_with_config_tagged<Tag: 'static>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(Message) + 'static,
+) {
+    use_provider::<Tag>(cx, url, config, move |_: &WsInner, msg| handler(msg));
+}
+
+// Provides the context for all `use_ws_context_provider*` hooks and returns
+// the handle.
+fn use_provider<Tag: 'static>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(&WsInner, Message) + 'static,
+) -> DioxusWs<Tag> {
+    let guard = cx.use_hook(|| {
+        // Always provide a handle, even if the socket can't be created, so
+        // that children keep rendering. It can be retried with `reconnect`.
+        let ws = DioxusWs::<Tag>::unconnected(url, config);
+        if let Err(err) = ws.inner.connect() {
             log::error!("Error creating WebSocket for {}: {}", url, err);
-            return;
+            ws.inner.gave_up.set(true);
+            ws.inner
+                .set_status(ConnectionState::Failed(err.to_string()));
+        }
+
+        ProviderGuard {
+            ws: cx.provide_context(ws),
+            url: url.to_string(),
         }
-        let ws = cx.provide_context(ws.unwrap());
-        ws.event_client.borrow_mut().set_on_message(Some(Box::new(
-            move |_client: &wasm_sockets::EventClient, message: Message| {
-                handler(message);
-            },
-        )));
     });
+
+    // Replace the handler on every render so that it sees the latest props
+    // and state, like any other event handler.
+    guard.ws.set_handler(Rc::new(handler));
+
+    if guard.url != url {
+        guard.url = url.to_string();
+        if let Err(err) = guard.ws.set_url(url) {
+            log::error!("Error connecting WebSocket to {}: {}", url, err);
+        }
+    }
+
+    guard.ws.clone()
 }
 
-/// Provide websocket context with a handler for incoming plaintext messages
-pub fn use_ws_context_provider_text(
+/// Like [`use_ws_context_provider_text`], for the connection identified by
+/// `Tag`.
+pub fn use_ws_context_provider_text_tagged<Tag: 'static>(
     cx: &ScopeState,
     url: &str,
     handler: impl Fn(String) + 'static,
@@ -117,32 +808,290 @@ pub fn use_ws_context_provider_text(
         }
     };
 
-    use_ws_context_provider(cx, url, handler)
+    use_ws_context_provider_tagged::<Tag>(cx, url, handler)
 }
 
-/// Provide websocket context with a handler for incoming JSON messages.
+/// Like [`use_ws_context_provider_json`], for the connection identified by
+/// `Tag`.
+pub fn use_ws_context_provider_json_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_with_config_tagged::<Tag, T>(cx, url, WsConfig::default(), handler)
+}
+
+/// Like [`use_ws_context_provider_json_with_config`], for the connection
+/// identified by `Tag`.
+pub fn use_ws_context_provider_json_with_config_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(T) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_provider::<Tag>(cx, url, config, json_handler(handler));
+}
+
+/// Provide websocket context with a handler for incoming JSON messages, and a
+/// `fallback` handler for messages that are valid JSON but not a valid `T`,
+/// e.g. enum variants added by a newer server.
+pub fn use_ws_context_provider_json_with_fallback<T>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(T) + 'static,
+    fallback: impl Fn(serde_json::Value) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_with_fallback_tagged::<(), T>(cx, url, handler, fallback)
+}
+
+/// Like [`use_ws_context_provider_json_with_fallback`], using the given
+/// connection options.
+pub fn use_ws_context_provider_json_with_fallback_with_config<T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(T) + 'static,
+    fallback: impl Fn(serde_json::Value) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_with_fallback_with_config_tagged::<(), T>(
+        cx, url, config, handler, fallback,
+    )
+}
+
+/// Like [`use_ws_context_provider_json_with_fallback`], for the connection
+/// identified by `Tag`.
+pub fn use_ws_context_provider_json_with_fallback_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(T) + 'static,
+    fallback: impl Fn(serde_json::Value) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_json_with_fallback_with_config_tagged::<Tag, T>(
+        cx,
+        url,
+        WsConfig::default(),
+        handler,
+        fallback,
+    )
+}
+
+/// Like [`use_ws_context_provider_json_with_fallback_with_config`], for the
+/// connection identified by `Tag`.
+pub fn use_ws_context_provider_json_with_fallback_with_config_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(T) + 'static,
+    fallback: impl Fn(serde_json::Value) + 'static,
+) where
+    T: for<'de> Deserialize<'de>,
+{
+    let handler = move |msg: Lenient<T>| match msg {
+        Lenient::Known(value) => handler(value),
+        Lenient::Fallback(value) => fallback(value),
+    };
+
+    use_ws_context_provider_json_with_config_tagged::<Tag, _>(cx, url, config, handler)
+}
+
+/// Provide websocket context with a handler for incoming MessagePack
+/// messages. Note that the message type T must implement Deserialize.
+#[cfg(feature = "msgpack")]
+pub fn use_ws_context_provider_msgpack<T>(cx: &ScopeState, url: &str, handler: impl Fn(T) + 'static)
+where
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_with_codec(cx, url, WsConfig::default(), MsgPack, handler)
+}
+
+/// Provide websocket context with a handler for incoming CBOR messages.
 /// Note that the message type T must implement Deserialize.
-pub fn use_ws_context_provider_json<T>(cx: &ScopeState, url: &str, handler: impl Fn(T) + 'static)
+#[cfg(feature = "cbor")]
+pub fn use_ws_context_provider_cbor<T>(cx: &ScopeState, url: &str, handler: impl Fn(T) + 'static)
 where
     T: for<'de> Deserialize<'de>,
 {
-    let handler = move |msg| match msg {
-        Message::Text(text) => {
-            let json = serde_json::from_str::<T>(&text);
+    use_ws_context_provider_with_codec(cx, url, WsConfig::default(), Cbor, handler)
+}
 
-            match json {
-                Ok(json) => handler(json),
-                Err(e) => log::error!("Error while deserializing websocket response: {}", e),
-            }
-        }
-        Message::Binary(_) => log::error!("Error: binary websocket message unsupported"),
-    };
+/// Provide websocket context with a handler for incoming messages decoded
+/// with `codec`, using the given connection options.
+///
+/// For JSON, use [`use_ws_context_provider_json_with_config`] instead. It
+/// decodes with [`WsConfig::json`], like `send_json` and the other hooks on
+/// the connection, while `codec` only applies to this handler.
+pub fn use_ws_context_provider_with_codec<C, T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    codec: C,
+    handler: impl Fn(T) + 'static,
+) where
+    C: Codec + 'static,
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_context_provider_with_codec_tagged::<(), C, T>(cx, url, config, codec, handler)
+}
 
-    use_ws_context_provider(cx, url, handler)
+/// Like [`use_ws_context_provider_with_codec`], for the connection
+/// identified by `Tag`.
+pub fn use_ws_context_provider_with_codec_tagged<Tag, C, T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    codec: C,
+    handler: impl Fn(T) + 'static,
+) where
+    Tag: 'static,
+    C: Codec + 'static,
+    T: for<'de> Deserialize<'de>,
+{
+    use_provider::<Tag>(cx, url, config, decoding_handler(codec, handler));
+}
+
+// Wraps a handler for decoded values into a handler for raw messages.
+fn decoding_handler<C, T>(
+    codec: C,
+    handler: impl Fn(T) + 'static,
+) -> impl Fn(&WsInner, Message) + 'static
+where
+    C: Codec + 'static,
+    T: for<'de> Deserialize<'de>,
+{
+    move |inner: &WsInner, msg: Message| match codec.decode::<T>(&msg) {
+        Ok(value) => handler(value),
+        Err(e) => inner.decode_failed(msg, e),
+    }
+}
+
+// Wraps a handler for decoded values into a handler for raw messages, decoding
+// with the connection's JSON settings.
+fn json_handler<T>(handler: impl Fn(T) + 'static) -> impl Fn(&WsInner, Message) + 'static
+where
+    T: for<'de> Deserialize<'de>,
+{
+    move |inner: &WsInner, msg: Message| match inner.config.json.decode::<T>(&msg) {
+        Ok(value) => handler(value),
+        Err(e) => inner.decode_failed(msg, e),
+    }
 }
 
 /// Consumes WebSocket context. Useful for sending messages over the WebSocket
 /// connection.
+///
+/// # Panics
+///
+/// Panics if no ancestor provides a WebSocket context, see
+/// [`try_use_ws_context`] for a non-panicking version.
 pub fn use_ws_context(cx: &ScopeState) -> DioxusWs {
-    cx.consume_context::<DioxusWs>().unwrap()
+    use_ws_context_tagged::<()>(cx)
+}
+
+/// Consumes WebSocket context, returning `None` if no ancestor provides one.
+pub fn try_use_ws_context(cx: &ScopeState) -> Option<DioxusWs> {
+    try_use_ws_context_tagged::<()>(cx)
+}
+
+/// Like [`use_ws_context`], for the connection identified by `Tag`.
+pub fn use_ws_context_tagged<Tag: 'static>(cx: &ScopeState) -> DioxusWs<Tag> {
+    try_use_ws_context_tagged::<Tag>(cx).expect("No WebSocket context provider found")
+}
+
+/// Like [`try_use_ws_context`], for the connection identified by `Tag`.
+pub fn try_use_ws_context_tagged<Tag: 'static>(cx: &ScopeState) -> Option<DioxusWs<Tag>> {
+    cx.consume_context::<DioxusWs<Tag>>()
+}
+
+/// Returns the state of the WebSocket connection. The calling component is
+/// re-rendered whenever the state changes.
+pub fn use_ws_status(cx: &ScopeState) -> ConnectionState {
+    use_ws_status_tagged::<()>(cx)
+}
+
+/// Like [`use_ws_status`], for the connection identified by `Tag`.
+pub fn use_ws_status_tagged<Tag: 'static>(cx: &ScopeState) -> ConnectionState {
+    let ws = use_ws_context_tagged::<Tag>(cx);
+
+    cx.use_hook(|| -> Subscription {
+        let update = cx.schedule_update();
+        ws.inner
+            .status_listeners
+            .subscribe(Rc::new(move |_: &ConnectionState| update()))
+    });
+
+    ws.status()
+}
+
+// These need a browser, run them with `wasm-pack test --headless --firefox`.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::cell::Cell;
+
+    use dioxus::core::{ScopeId, VirtualDom};
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    thread_local! {
+        static VERSION: Cell<u32> = const { Cell::new(1) };
+        static CALLED_WITH: Cell<Option<u32>> = const { Cell::new(None) };
+        static WS: RefCell<Option<DioxusWs>> = const { RefCell::new(None) };
+    }
+
+    fn app(cx: Scope) -> Element {
+        let version = VERSION.with(Cell::get);
+        cx.render(rsx!(Provider { version: version }))
+    }
+
+    #[derive(Props, PartialEq)]
+    struct ProviderProps {
+        version: u32,
+    }
+
+    #[allow(non_snake_case)]
+    fn Provider(cx: Scope<ProviderProps>) -> Element {
+        let version = cx.props.version;
+        use_ws_context_provider(cx, "ws://localhost:1", move |_| {
+            CALLED_WITH.with(|called| called.set(Some(version)))
+        });
+
+        cx.render(rsx!(Consumer {}))
+    }
+
+    #[allow(non_snake_case)]
+    fn Consumer(cx: Scope) -> Element {
+        let ws = use_ws_context(cx);
+        WS.with(|slot| slot.replace(Some(ws)));
+
+        None
+    }
+
+    #[wasm_bindgen_test]
+    fn handler_sees_latest_props() {
+        let mut dom = VirtualDom::new(app);
+        let _ = dom.rebuild();
+
+        VERSION.with(|version| version.set(2));
+        dom.mark_dirty(ScopeId(0));
+        let _ = dom.render_immediate();
+
+        let ws = WS
+            .with(|slot| slot.borrow().clone())
+            .expect("provider was rendered");
+        ws.inner.receive(Message::Text(String::new()));
+
+        assert_eq!(CALLED_WITH.with(Cell::get), Some(2));
+    }
 }
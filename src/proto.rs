@@ -0,0 +1,167 @@
+use dioxus::prelude::*;
+use prost_types::Any;
+use wasm_sockets::Message;
+
+use crate::{use_provider, CodecError, DioxusWs, SendError, SendOutcome, WsConfig, WsInner};
+
+/// How protobuf messages are laid out in WebSocket binary frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProtoFraming {
+    /// Each frame holds exactly one message.
+    #[default]
+    Plain,
+    /// Each frame holds one or more messages, each prefixed with its length
+    /// as a varint.
+    LengthDelimited,
+}
+
+impl<Tag> DioxusWs<Tag> {
+    /// Sends a protobuf message as a binary message.
+    pub fn send_proto<T: prost::Message>(&self, value: &T) -> Result<SendOutcome, SendError> {
+        self.send_proto_framed(ProtoFraming::Plain, value)
+    }
+
+    /// Sends a protobuf message as a binary message, using the given framing.
+    pub fn send_proto_framed<T: prost::Message>(
+        &self,
+        framing: ProtoFraming,
+        value: &T,
+    ) -> Result<SendOutcome, SendError> {
+        let bytes = match framing {
+            ProtoFraming::Plain => value.encode_to_vec(),
+            ProtoFraming::LengthDelimited => value.encode_length_delimited_to_vec(),
+        };
+        self.send(Message::Binary(bytes))
+    }
+
+    /// Sends a protobuf message wrapped in a `google.protobuf.Any` envelope
+    /// with the given type url, so that several message types can share one
+    /// connection.
+    pub fn send_proto_any<T: prost::Message>(
+        &self,
+        type_url: &str,
+        value: &T,
+    ) -> Result<SendOutcome, SendError> {
+        let any = Any {
+            type_url: type_url.to_string(),
+            value: value.encode_to_vec(),
+        };
+        self.send_proto(&any)
+    }
+}
+
+/// Provide websocket context with a handler for incoming protobuf messages.
+pub fn use_ws_context_provider_proto<T>(cx: &ScopeState, url: &str, handler: impl Fn(T) + 'static)
+where
+    T: prost::Message + Default,
+{
+    use_ws_context_provider_proto_tagged::<(), T>(cx, url, handler)
+}
+
+/// Like [`use_ws_context_provider_proto`], for the connection identified by
+/// `Tag`.
+pub fn use_ws_context_provider_proto_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(T) + 'static,
+) where
+    T: prost::Message + Default,
+{
+    use_ws_context_provider_proto_with_config_tagged::<Tag, T>(
+        cx,
+        url,
+        WsConfig::default(),
+        ProtoFraming::Plain,
+        handler,
+    )
+}
+
+/// Provide websocket context with a handler for incoming protobuf messages
+/// laid out with the given framing. With
+/// [`ProtoFraming::LengthDelimited`], the handler is called once for every
+/// message in a frame.
+pub fn use_ws_context_provider_proto_framed<T>(
+    cx: &ScopeState,
+    url: &str,
+    framing: ProtoFraming,
+    handler: impl Fn(T) + 'static,
+) where
+    T: prost::Message + Default,
+{
+    use_ws_context_provider_proto_with_config(cx, url, WsConfig::default(), framing, handler)
+}
+
+/// Provide websocket context with a handler for incoming protobuf messages
+/// laid out with the given framing, using the given connection options.
+pub fn use_ws_context_provider_proto_with_config<T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    framing: ProtoFraming,
+    handler: impl Fn(T) + 'static,
+) where
+    T: prost::Message + Default,
+{
+    use_ws_context_provider_proto_with_config_tagged::<(), T>(cx, url, config, framing, handler)
+}
+
+/// Like [`use_ws_context_provider_proto_with_config`], for the connection
+/// identified by `Tag`.
+pub fn use_ws_context_provider_proto_with_config_tagged<Tag: 'static, T>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    framing: ProtoFraming,
+    handler: impl Fn(T) + 'static,
+) where
+    T: prost::Message + Default,
+{
+    let handler = move |inner: &WsInner, msg: Message| {
+        if let Err(e) = decode_proto::<T>(framing, &msg, &handler) {
+            inner.decode_failed(msg, e);
+        }
+    };
+
+    use_provider::<Tag>(cx, url, config, handler);
+}
+
+/// Provide websocket context with a handler for incoming protobuf messages
+/// wrapped in `google.protobuf.Any` envelopes. The handler can dispatch on
+/// `type_url` and decode `value` into the matching message type.
+///
+/// For connection options or a tag, use
+/// [`use_ws_context_provider_proto_with_config`] with `T = Any`.
+pub fn use_ws_context_provider_proto_any(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(Any) + 'static,
+) {
+    use_ws_context_provider_proto(cx, url, handler)
+}
+
+// Decodes every message in a frame, calling `handler` for each one.
+fn decode_proto<T>(
+    framing: ProtoFraming,
+    message: &Message,
+    handler: &impl Fn(T),
+) -> Result<(), CodecError>
+where
+    T: prost::Message + Default,
+{
+    let mut bytes = match message {
+        Message::Binary(bytes) => bytes.as_slice(),
+        Message::Text(_) => return Err(CodecError::UnexpectedText),
+    };
+
+    match framing {
+        ProtoFraming::Plain => handler(T::decode(bytes).map_err(CodecError::Protobuf)?),
+        ProtoFraming::LengthDelimited => {
+            while !bytes.is_empty() {
+                let value = T::decode_length_delimited(&mut bytes).map_err(CodecError::Protobuf)?;
+                handler(value);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::{collections::VecDeque, time::Duration};
+
+use wasm_sockets::Message;
+
+use crate::timer;
+
+/// What to do when a message is sent while the outbound queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the message that has been queued the longest.
+    DropOldest,
+    /// Discard the most recently queued message.
+    DropNewest,
+    /// Refuse the message being sent.
+    Reject,
+}
+
+/// Controls buffering of messages sent while the socket isn't open.
+///
+/// Queued messages are sent in order as soon as the connection opens.
+#[derive(Clone, Debug)]
+pub struct QueueConfig {
+    /// Maximum number of queued messages. Zero disables queueing.
+    pub capacity: usize,
+    /// What to do when the queue is full.
+    pub overflow: OverflowPolicy,
+    /// How long a message may wait in the queue before it is discarded, or
+    /// `None` to keep messages until they are sent.
+    pub ttl: Option<Duration>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig {
+            capacity: 64,
+            overflow: OverflowPolicy::DropOldest,
+            ttl: None,
+        }
+    }
+}
+
+struct Queued {
+    message: Message,
+    // Milliseconds since the Unix epoch.
+    expires_at: Option<f64>,
+}
+
+#[derive(Default)]
+pub(crate) struct OutboundQueue {
+    messages: VecDeque<Queued>,
+}
+
+impl OutboundQueue {
+    /// Queues `message`, returning it back if it was rejected.
+    pub(crate) fn push(&mut self, config: &QueueConfig, message: Message) -> Result<(), Message> {
+        if config.capacity == 0 {
+            return Err(message);
+        }
+
+        let now = timer::now();
+        self.remove_expired(now);
+
+        if self.messages.len() >= config.capacity {
+            match config.overflow {
+                OverflowPolicy::DropOldest => {
+                    self.messages.pop_front();
+                }
+                OverflowPolicy::DropNewest => {
+                    self.messages.pop_back();
+                }
+                OverflowPolicy::Reject => return Err(message),
+            }
+            log::warn!("WebSocket send queue full, dropped a queued message.");
+        }
+
+        self.messages.push_back(Queued {
+            message,
+            expires_at: config.ttl.map(|ttl| now + ttl.as_secs_f64() * 1000.0),
+        });
+
+        Ok(())
+    }
+
+    /// Removes and returns all unexpired messages, oldest first.
+    pub(crate) fn take(&mut self) -> Vec<Message> {
+        self.remove_expired(timer::now());
+        self.messages
+            .drain(..)
+            .map(|queued| queued.message)
+            .collect()
+    }
+
+    /// Returns true if another message would overflow the queue.
+    pub(crate) fn is_full(&mut self, config: &QueueConfig) -> bool {
+        self.remove_expired(timer::now());
+        config.capacity > 0 && self.messages.len() >= config.capacity
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    fn remove_expired(&mut self, now: f64) {
+        let before = self.messages.len();
+        self.messages
+            .retain(|queued| queued.expires_at.is_none_or(|expires_at| expires_at > now));
+
+        let expired = before - self.messages.len();
+        if expired > 0 {
+            log::warn!("Discarded {} expired queued WebSocket messages.", expired);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::{rc::Rc, time::Duration};
+
+/// Controls how a [`DioxusWs`](crate::DioxusWs) reconnects after its socket
+/// closes unexpectedly.
+///
+/// The delay before attempt `n` (starting at 0) is
+/// `initial_delay * multiplier^n`, capped at `max_delay`, and then randomly
+/// spread by up to `jitter` (a fraction of the delay) in either direction so
+/// that many clients don't reconnect in lockstep.
+#[derive(Clone)]
+pub struct ReconnectConfig {
+    /// Delay before the first reconnection attempt.
+    pub initial_delay: Duration,
+    /// Factor the delay is multiplied by after every failed attempt.
+    pub multiplier: f64,
+    /// Upper bound for the delay between attempts, before jitter.
+    pub max_delay: Duration,
+    /// Random spread applied to each delay, as a fraction between 0 and 1.
+    pub jitter: f64,
+    /// Number of consecutive attempts before giving up, or `None` to retry
+    /// forever.
+    pub max_attempts: Option<u32>,
+    /// Called once when `max_attempts` is exhausted.
+    pub on_give_up: Option<Rc<dyn Fn()>>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            initial_delay: Duration::from_millis(500),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+            max_attempts: None,
+            on_give_up: None,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// A config that never reconnects.
+    pub fn disabled() -> Self {
+        ReconnectConfig {
+            max_attempts: Some(0),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the delay before reconnection attempt `attempt`, or `None` if
+    /// we should give up.
+    pub(crate) fn delay(&self, attempt: u32) -> Option<Duration> {
+        if matches!(self.max_attempts, Some(max) if attempt >= max) {
+            return None;
+        }
+
+        let exponent = attempt.min(i32::MAX as u32) as i32;
+        let base = self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let base = base.min(self.max_delay.as_secs_f64());
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let spread = 1.0 + jitter * (2.0 * js_sys::Math::random() - 1.0);
+
+        // Saturate rather than panic for huge delays, e.g. a `max_delay` of
+        // `Duration::MAX` spread upwards by jitter.
+        let delay = Duration::try_from_secs_f64((base * spread).max(0.0)).unwrap_or(Duration::MAX);
+        Some(delay)
+    }
+
+    /// Returns true if this config never reconnects, see [`disabled`](Self::disabled).
+    pub(crate) fn is_disabled(&self) -> bool {
+        self.max_attempts == Some(0)
+    }
+}
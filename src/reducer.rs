@@ -0,0 +1,69 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+
+use crate::{subscribe::use_message_callback, use_ws_context_tagged, Codec};
+
+/// Folds incoming messages into component state, e.g. to maintain an order
+/// book or a chat log.
+///
+/// The state starts out as `init()`. Every message that decodes as JSON into
+/// `T` (with the connection's [`WsConfig::json`](crate::WsConfig::json)
+/// codec) is applied with `reducer`. The calling component is re-rendered
+/// once for any number of messages that arrive before its next render.
+///
+/// Returns a snapshot of the state. The state is only cloned when a message
+/// arrives while an earlier snapshot is still held, e.g. by an event handler.
+pub fn use_ws_reducer<S, T>(
+    cx: &ScopeState,
+    init: impl FnOnce() -> S,
+    reducer: impl Fn(&mut S, T) + 'static,
+) -> Rc<S>
+where
+    S: Clone + 'static,
+    T: for<'de> Deserialize<'de>,
+{
+    use_ws_reducer_tagged::<(), S, T>(cx, init, reducer)
+}
+
+/// Like [`use_ws_reducer`], for the connection identified by `Tag`.
+pub fn use_ws_reducer_tagged<Tag: 'static, S, T>(
+    cx: &ScopeState,
+    init: impl FnOnce() -> S,
+    reducer: impl Fn(&mut S, T) + 'static,
+) -> Rc<S>
+where
+    S: Clone + 'static,
+    T: for<'de> Deserialize<'de>,
+{
+    let ws = use_ws_context_tagged::<Tag>(cx);
+    let json = ws.inner.config.json;
+
+    let state = cx
+        .use_hook(|| Rc::new(RefCell::new(Rc::new(init()))))
+        .clone();
+    let pending = cx.use_hook(|| Rc::new(Cell::new(false))).clone();
+    let update = cx.use_hook(|| cx.schedule_update()).clone();
+
+    // This render picks up everything applied so far.
+    pending.set(false);
+
+    {
+        let state = state.clone();
+        use_message_callback(cx, &ws, move |msg| {
+            if let Ok(value) = json.decode::<T>(msg) {
+                reducer(Rc::make_mut(&mut state.borrow_mut()), value);
+                if !pending.replace(true) {
+                    update();
+                }
+            }
+        });
+    }
+
+    let snapshot = state.borrow().clone();
+    snapshot
+}
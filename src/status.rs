@@ -0,0 +1,29 @@
+/// The state of a [`DioxusWs`](crate::DioxusWs) connection, as returned by
+/// [`use_ws_status`](crate::use_ws_status).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// The socket is being opened.
+    Connecting,
+    /// The socket is open and messages can be sent.
+    Open,
+    /// A close was requested and the socket is shutting down.
+    Closing,
+    /// The socket was closed.
+    Closed {
+        code: u16,
+        reason: String,
+        was_clean: bool,
+    },
+    /// The socket closed and a reconnection attempt is scheduled.
+    /// `next_retry_at` is in milliseconds since the Unix epoch.
+    Reconnecting { attempt: u32, next_retry_at: f64 },
+    /// The connection failed or reconnection was given up on.
+    Failed(String),
+}
+
+impl ConnectionState {
+    /// Returns true if messages can currently be sent.
+    pub fn is_open(&self) -> bool {
+        matches!(self, ConnectionState::Open)
+    }
+}
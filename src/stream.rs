@@ -0,0 +1,114 @@
+use std::{
+    cell::RefCell,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use futures::{channel::mpsc, Sink, Stream, StreamExt};
+use wasm_sockets::Message;
+
+use crate::{subscribers::Subscription, DioxusWs, SendError};
+
+impl<Tag> DioxusWs<Tag> {
+    /// Returns a Stream of all incoming messages, buffering up to `capacity`
+    /// of them. Messages arriving while the buffer is full are dropped.
+    ///
+    /// Every receiver stays subscribed until it is dropped, so create it once,
+    /// e.g. in `use_future`, rather than on every render.
+    pub fn receiver(&self, capacity: usize) -> WsReceiver {
+        let (sender, messages) = mpsc::channel(capacity);
+
+        // Dropping the sender right away ends the stream.
+        let subscription = if self.inner.shut_down.get() {
+            None
+        } else {
+            let sender = RefCell::new(sender);
+            Some(
+                self.inner
+                    .subscribers
+                    .subscribe(Rc::new(move |msg: &Message| {
+                        if let Err(err) = sender.borrow_mut().try_send(msg.clone()) {
+                            if err.is_full() {
+                                log::error!("WebSocket receiver is full, dropping message");
+                            }
+                        }
+                    })),
+            )
+        };
+
+        WsReceiver {
+            messages,
+            _subscription: subscription,
+        }
+    }
+
+    /// Returns a Sink for outgoing messages. Messages are passed on to
+    /// [`send`](Self::send), so they are buffered in the connection's bounded
+    /// outbound queue while the socket isn't open, see
+    /// [`WsConfig::queue`](crate::WsConfig::queue). The sink waits while that
+    /// queue is full instead of dropping messages.
+    pub fn sender(&self) -> WsSender<Tag> {
+        WsSender { ws: self.clone() }
+    }
+}
+
+/// Incoming messages of a [`DioxusWs`] as a [`Stream`], see
+/// [`DioxusWs::receiver`].
+///
+/// The stream ends once the connection shuts down.
+pub struct WsReceiver {
+    messages: mpsc::Receiver<Message>,
+    _subscription: Option<Subscription>,
+}
+
+impl Stream for WsReceiver {
+    type Item = Message;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Message>> {
+        self.messages.poll_next_unpin(cx)
+    }
+}
+
+/// Outgoing messages of a [`DioxusWs`] as a [`Sink`], see
+/// [`DioxusWs::sender`].
+///
+/// Sending waits while the socket isn't open and the outbound queue is full,
+/// and fails with the same errors as [`DioxusWs::send`].
+pub struct WsSender<Tag = ()> {
+    ws: DioxusWs<Tag>,
+}
+
+impl<Tag> Clone for WsSender<Tag> {
+    fn clone(&self) -> Self {
+        WsSender {
+            ws: self.ws.clone(),
+        }
+    }
+}
+
+impl<Tag> Sink<Message> for WsSender<Tag> {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+        let inner = &self.ws.inner;
+        if inner.queue_full() {
+            inner.send_wakers.borrow_mut().push(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: Message) -> Result<(), SendError> {
+        self.ws.send(msg).map(|_| ())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+        Poll::Ready(Ok(()))
+    }
+}
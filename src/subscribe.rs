@@ -0,0 +1,64 @@
+use std::{cell::RefCell, rc::Rc};
+
+use dioxus::prelude::*;
+use wasm_sockets::Message;
+
+use crate::{subscribers::Subscription, use_ws_context_tagged, DioxusWs};
+
+type Callback = Rc<RefCell<Option<Rc<dyn Fn(&Message)>>>>;
+
+/// Registers `handler` for incoming messages on the WebSocket context, in
+/// addition to the provider's handler. Only messages for which `filter`
+/// returns true are passed on.
+///
+/// Any number of components can subscribe. The handler is unregistered when
+/// the calling component unmounts.
+pub fn use_ws_subscribe(
+    cx: &ScopeState,
+    filter: impl Fn(&Message) -> bool + 'static,
+    handler: impl Fn(Message) + 'static,
+) {
+    use_ws_subscribe_tagged::<()>(cx, filter, handler)
+}
+
+/// Like [`use_ws_subscribe`], for the connection identified by `Tag`.
+pub fn use_ws_subscribe_tagged<Tag: 'static>(
+    cx: &ScopeState,
+    filter: impl Fn(&Message) -> bool + 'static,
+    handler: impl Fn(Message) + 'static,
+) {
+    let ws = use_ws_context_tagged::<Tag>(cx);
+
+    use_message_callback(cx, &ws, move |msg| {
+        if filter(msg) {
+            handler(msg.clone());
+        }
+    });
+}
+
+// Calls `callback` with every incoming message on `ws` until the calling
+// component unmounts. The callback is replaced on every render so that it
+// sees the latest props and state, like any other event handler.
+pub(crate) fn use_message_callback<Tag>(
+    cx: &ScopeState,
+    ws: &DioxusWs<Tag>,
+    callback: impl Fn(&Message) + 'static,
+) {
+    let (current, _subscription) = cx.use_hook(|| -> (Callback, Subscription) {
+        let current: Callback = Rc::new(RefCell::new(None));
+        let subscription = {
+            let current = current.clone();
+            ws.inner
+                .subscribers
+                .subscribe(Rc::new(move |msg: &Message| {
+                    let callback = current.borrow().clone();
+                    if let Some(callback) = callback {
+                        callback(msg);
+                    }
+                }))
+        };
+        (current, subscription)
+    });
+
+    current.replace(Some(Rc::new(callback)));
+}
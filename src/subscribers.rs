@@ -0,0 +1,88 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+type Callback<A> = Rc<dyn Fn(&A)>;
+
+/// A list of callbacks that are invoked with every emitted value.
+pub(crate) struct Subscribers<A: ?Sized> {
+    list: Rc<RefCell<SubscriberList<A>>>,
+}
+
+struct SubscriberList<A: ?Sized> {
+    next_id: usize,
+    entries: Vec<(usize, Callback<A>)>,
+}
+
+impl<A: ?Sized + 'static> Subscribers<A> {
+    pub(crate) fn new() -> Self {
+        Subscribers {
+            list: Rc::new(RefCell::new(SubscriberList {
+                next_id: 0,
+                entries: Vec::new(),
+            })),
+        }
+    }
+
+    /// Registers `callback`. It stays registered until the returned
+    /// [`Subscription`] is dropped.
+    pub(crate) fn subscribe(&self, callback: Callback<A>) -> Subscription {
+        let id = {
+            let mut list = self.list.borrow_mut();
+            let id = list.next_id;
+            list.next_id += 1;
+            list.entries.push((id, callback));
+            id
+        };
+
+        let list = Rc::downgrade(&self.list);
+        Subscription {
+            unsubscribe: Some(Box::new(move || unsubscribe(&list, id))),
+        }
+    }
+
+    /// Unregisters all callbacks.
+    pub(crate) fn clear(&self) {
+        // Dropping a callback may unsubscribe, so don't hold the borrow while
+        // doing so.
+        let entries = std::mem::take(&mut self.list.borrow_mut().entries);
+        drop(entries);
+    }
+
+    /// Calls every registered callback with `value`.
+    pub(crate) fn emit(&self, value: &A) {
+        // Callbacks may subscribe or unsubscribe, so don't hold the borrow
+        // while calling them.
+        let callbacks: Vec<Callback<A>> = self
+            .list
+            .borrow()
+            .entries
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect();
+
+        for callback in callbacks {
+            callback(value);
+        }
+    }
+}
+
+fn unsubscribe<A: ?Sized>(list: &Weak<RefCell<SubscriberList<A>>>, id: usize) {
+    if let Some(list) = list.upgrade() {
+        list.borrow_mut().entries.retain(|(entry, _)| *entry != id);
+    }
+}
+
+/// Keeps a callback registered with [`Subscribers`] until dropped.
+pub(crate) struct Subscription {
+    unsubscribe: Option<Box<dyn FnOnce()>>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.take() {
+            unsubscribe();
+        }
+    }
+}
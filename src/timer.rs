@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// Milliseconds since the Unix epoch, as reported by `Date.now()`.
+pub(crate) fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+/// A pending `setTimeout` callback. The callback is cancelled if this is
+/// dropped before it fires.
+pub(crate) struct Timeout {
+    handle: i32,
+}
+
+impl Timeout {
+    /// Schedules `callback` to run after `delay`. Returns `None` if there is no
+    /// `window` to schedule on.
+    pub(crate) fn new(delay: Duration, callback: impl FnOnce() + 'static) -> Option<Timeout> {
+        let window = web_sys::window()?;
+        let callback = Closure::once_into_js(callback);
+        let millis = delay.as_millis().min(i32::MAX as u128) as i32;
+
+        match window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), millis)
+        {
+            Ok(handle) => Some(Timeout { handle }),
+            Err(err) => {
+                log::error!("Error scheduling timeout: {:?}", err);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            window.clear_timeout_with_handle(self.handle);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::marker::PhantomData;
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{json_handler, use_provider, DioxusWs, SendError, SendOutcome, WsConfig};
+
+/// A [`DioxusWs`] handle that can only send values of type `Out`.
+///
+/// The outgoing type is fixed by [`use_typed_ws_context_provider`], so
+/// sending anything else is a compile error.
+pub struct TypedWs<Out, Tag = ()> {
+    ws: DioxusWs<Tag>,
+    _out: PhantomData<fn(&Out)>,
+}
+
+impl<Out, Tag> Clone for TypedWs<Out, Tag> {
+    fn clone(&self) -> Self {
+        TypedWs {
+            ws: self.ws.clone(),
+            _out: PhantomData,
+        }
+    }
+}
+
+impl<Out: Serialize, Tag> TypedWs<Out, Tag> {
+    /// Sends `value` as JSON, see [`WsConfig::json`].
+    pub fn send(&self, value: &Out) -> Result<SendOutcome, SendError> {
+        self.ws.send_json(value)
+    }
+
+    /// Returns the untyped handle, e.g. to check the connection status.
+    pub fn raw(&self) -> &DioxusWs<Tag> {
+        &self.ws
+    }
+}
+
+/// Provide websocket context with a handler for incoming JSON messages of
+/// type `In`, fixing the outgoing message type to `Out`.
+///
+/// Children can get a handle that sends `Out` with [`use_typed_ws_context`].
+/// The untyped [`use_ws_context`](crate::use_ws_context) keeps working too.
+pub fn use_typed_ws_context_provider<In, Out>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(In) + 'static,
+) where
+    In: for<'de> Deserialize<'de>,
+    Out: Serialize + 'static,
+{
+    use_typed_ws_context_provider_tagged::<(), In, Out>(cx, url, handler)
+}
+
+/// Like [`use_typed_ws_context_provider`], using the given connection
+/// options. Both directions use [`WsConfig::json`].
+pub fn use_typed_ws_context_provider_with_config<In, Out>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(In) + 'static,
+) where
+    In: for<'de> Deserialize<'de>,
+    Out: Serialize + 'static,
+{
+    use_typed_ws_context_provider_with_config_tagged::<(), In, Out>(cx, url, config, handler)
+}
+
+/// Like [`use_typed_ws_context_provider`], for the connection identified by
+/// `Tag`.
+pub fn use_typed_ws_context_provider_tagged<Tag, In, Out>(
+    cx: &ScopeState,
+    url: &str,
+    handler: impl Fn(In) + 'static,
+) where
+    Tag: 'static,
+    In: for<'de> Deserialize<'de>,
+    Out: Serialize + 'static,
+{
+    use_typed_ws_context_provider_with_config_tagged::<Tag, In, Out>(
+        cx,
+        url,
+        WsConfig::default(),
+        handler,
+    )
+}
+
+/// Like [`use_typed_ws_context_provider_with_config`], for the connection
+/// identified by `Tag`.
+pub fn use_typed_ws_context_provider_with_config_tagged<Tag, In, Out>(
+    cx: &ScopeState,
+    url: &str,
+    config: WsConfig,
+    handler: impl Fn(In) + 'static,
+) where
+    Tag: 'static,
+    In: for<'de> Deserialize<'de>,
+    Out: Serialize + 'static,
+{
+    let ws = use_provider::<Tag>(cx, url, config, json_handler(handler));
+
+    cx.use_hook(|| {
+        cx.provide_context(TypedWs::<Out, Tag> {
+            ws,
+            _out: PhantomData,
+        })
+    });
+}
+
+/// Consumes typed WebSocket context for sending `Out` messages.
+///
+/// # Panics
+///
+/// Panics if no ancestor provides a typed WebSocket context for `Out`, see
+/// [`try_use_typed_ws_context`] for a non-panicking version.
+pub fn use_typed_ws_context<Out: 'static>(cx: &ScopeState) -> TypedWs<Out> {
+    use_typed_ws_context_tagged::<(), Out>(cx)
+}
+
+/// Consumes typed WebSocket context, returning `None` if no ancestor provides
+/// one for `Out`.
+pub fn try_use_typed_ws_context<Out: 'static>(cx: &ScopeState) -> Option<TypedWs<Out>> {
+    try_use_typed_ws_context_tagged::<(), Out>(cx)
+}
+
+/// Like [`use_typed_ws_context`], for the connection identified by `Tag`.
+pub fn use_typed_ws_context_tagged<Tag: 'static, Out: 'static>(
+    cx: &ScopeState,
+) -> TypedWs<Out, Tag> {
+    try_use_typed_ws_context_tagged::<Tag, Out>(cx)
+        .expect("No typed WebSocket context provider found for this message type")
+}
+
+/// Like [`try_use_typed_ws_context`], for the connection identified by `Tag`.
+pub fn try_use_typed_ws_context_tagged<Tag: 'static, Out: 'static>(
+    cx: &ScopeState,
+) -> Option<TypedWs<Out, Tag>> {
+    cx.consume_context::<TypedWs<Out, Tag>>()
+}